@@ -0,0 +1,42 @@
+//! Compare the fixed-point color math with the float versions on the host:
+//! `cargo host-bench`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use tp_led_matrix::{Color, Image, Image8x8};
+
+const ITERATIONS: u32 = 100_000;
+
+/// Run `f` `ITERATIONS` times and print the average time of a call.
+fn bench<T>(name: &str, mut f: impl FnMut() -> T) -> Duration {
+    for _ in 0..ITERATIONS / 10 {
+        black_box(f());
+    }
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(f());
+    }
+    let average = start.elapsed() / ITERATIONS;
+    println!("{:<24} {:>10.1?}", name, average);
+    average
+}
+
+fn main() {
+    let image = Image8x8::gradient(Color { r: 0xff, g: 0x80, b: 0x20 });
+    let color = Color { r: 0xc0, g: 0x40, b: 0x10 };
+    let scale = 200u8;
+
+    let float = bench("Color * f32", || black_box(color) * (black_box(scale) as f32 / 255.0));
+    let fixed = bench("Color::scale8", || black_box(color).scale8(black_box(scale)));
+    println!("{:<24} {:>10.2}x", "speedup", float.as_secs_f64() / fixed.as_secs_f64());
+
+    let float = bench("Image pixels * f32", || {
+        let factor = black_box(scale) as f32 / 255.0;
+        Image(black_box(&image).0.map(|row| row.map(|pixel| pixel * factor)))
+    });
+    let fixed = bench("Image::scale8", || black_box(&image).scale8(black_box(scale)));
+    println!("{:<24} {:>10.2}x", "speedup", float.as_secs_f64() / fixed.as_secs_f64());
+
+    bench("Image::gradient", || Image8x8::gradient(black_box(color)));
+}
@@ -1,5 +1,6 @@
 MEMORY
 {
-  FLASH : ORIGIN = 0x08000000, LENGTH = 1M
+  /* The last 2K page keeps the white balance, see src/main.rs */
+  FLASH : ORIGIN = 0x08000000, LENGTH = 1M - 2K
   RAM   : ORIGIN = 0x20000000, LENGTH = 96K
 }
@@ -0,0 +1,258 @@
+//! Animation files: frames with their timing and metadata, unlike the raw
+//! SE203 streams of `many_frames.bin`.
+//!
+//! All integers are unsigned and stored most significant byte first:
+//!
+//! | Size        | Field                                                  |
+//! |-------------|--------------------------------------------------------|
+//! | 4           | magic number `SEAN`                                    |
+//! | 1           | format version, currently 1                            |
+//! | 1           | pixel format, 0 for 8-bit RGB                          |
+//! | 2           | width in pixels                                        |
+//! | 2           | height in pixels                                       |
+//! | 4           | number of frames                                       |
+//! | 2           | number of times the animation is played, 0 for forever |
+//! | 1           | length of the name in bytes, 0 if none                 |
+//! | name length | name, in UTF-8                                         |
+//!
+//! Every frame follows as its duration in milliseconds on 2 bytes, then its
+//! pixels row by row.
+
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use crate::protocol::{self, Checksum, Decoder, Framing, Packet};
+use crate::Image;
+
+/// First bytes of every animation file.
+pub const MAGIC: [u8; 4] = *b"SEAN";
+
+/// Version of the format written by `Animation::write()`.
+pub const VERSION: u8 = 1;
+
+/// Encoding of the pixels of a frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// One byte per channel, in RGB order.
+    Rgb888,
+}
+
+impl PixelFormat {
+    /// Returns the identifier of the format in files.
+    pub fn id(&self) -> u8 {
+        match self {
+            PixelFormat::Rgb888 => 0,
+        }
+    }
+
+    /// Returns the format with the given identifier, if any.
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(PixelFormat::Rgb888),
+            _ => None,
+        }
+    }
+
+    /// Number of bytes of a pixel.
+    pub fn bytes_per_pixel(&self) -> usize {
+        match self {
+            PixelFormat::Rgb888 => 3,
+        }
+    }
+}
+
+/// One image of an animation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    pub duration_ms: u16,
+    /// Pixels row by row, in the pixel format of the animation.
+    pub pixels: Vec<u8>,
+}
+
+/// Sequence of frames and how to play them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Animation {
+    pub width: u16,
+    pub height: u16,
+    pub format: PixelFormat,
+    /// Number of times the animation is played, 0 for forever.
+    pub loop_count: u16,
+    pub name: Option<String>,
+    pub frames: Vec<Frame>,
+}
+
+/// Invalid animation file, or animation which cannot be converted.
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// The file does not start with `MAGIC`.
+    BadMagic,
+    UnsupportedVersion(u8),
+    UnknownPixelFormat(u8),
+    /// The name is not valid UTF-8, or longer than 255 bytes.
+    InvalidName,
+    /// A frame does not hold width × height pixels.
+    FrameSize { index: usize, len: usize },
+    /// Only 8×8 RGB animations can be sent as SE203 frames.
+    NotSe203,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::BadMagic => write!(f, "not an animation file"),
+            Error::UnsupportedVersion(version) => write!(f, "unsupported version {}", version),
+            Error::UnknownPixelFormat(id) => write!(f, "unknown pixel format {}", id),
+            Error::InvalidName => write!(f, "invalid name"),
+            Error::FrameSize { index, len } => write!(f, "frame {} has {} bytes", index, len),
+            Error::NotSe203 => write!(f, "only 8×8 RGB animations can be sent as SE203 frames"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+fn read_u8(r: &mut impl Read) -> io::Result<u8> {
+    let mut bytes = [0; 1];
+    r.read_exact(&mut bytes)?;
+    Ok(bytes[0])
+}
+
+fn read_u16(r: &mut impl Read) -> io::Result<u16> {
+    let mut bytes = [0; 2];
+    r.read_exact(&mut bytes)?;
+    Ok(u16::from_be_bytes(bytes))
+}
+
+fn read_u32(r: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    r.read_exact(&mut bytes)?;
+    Ok(u32::from_be_bytes(bytes))
+}
+
+impl Animation {
+    /// Create an 8×8 RGB animation played forever, showing every image for
+    /// `duration_ms`.
+    pub fn from_images(images: &[Image], duration_ms: u16) -> Self {
+        let frames = images
+            .iter()
+            .map(|image| Frame { duration_ms, pixels: image.as_ref().to_vec() })
+            .collect();
+        Animation { width: 8, height: 8, format: PixelFormat::Rgb888, loop_count: 0, name: None, frames }
+    }
+
+    /// Number of bytes of the pixels of a frame.
+    pub fn frame_len(&self) -> usize {
+        self.width as usize * self.height as usize * self.format.bytes_per_pixel()
+    }
+
+    /// Read an animation file.
+    pub fn read(mut r: impl Read) -> Result<Self, Error> {
+        let mut magic = [0; 4];
+        r.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(Error::BadMagic);
+        }
+        let version = read_u8(&mut r)?;
+        if version != VERSION {
+            return Err(Error::UnsupportedVersion(version));
+        }
+        let id = read_u8(&mut r)?;
+        let format = PixelFormat::from_id(id).ok_or(Error::UnknownPixelFormat(id))?;
+        let width = read_u16(&mut r)?;
+        let height = read_u16(&mut r)?;
+        let count = read_u32(&mut r)?;
+        let loop_count = read_u16(&mut r)?;
+        let mut name = vec![0; read_u8(&mut r)? as usize];
+        r.read_exact(&mut name)?;
+        let name = match name.is_empty() {
+            true => None,
+            false => Some(String::from_utf8(name).map_err(|_| Error::InvalidName)?),
+        };
+        let mut animation = Animation { width, height, format, loop_count, name, frames: Vec::new() };
+        // The header is not trusted: the pixels are only allocated as they
+        // are read, so that a truncated file fails instead of allocating the
+        // size it claims
+        let len = width as u64 * height as u64 * format.bytes_per_pixel() as u64;
+        for _ in 0..count {
+            let duration_ms = read_u16(&mut r)?;
+            let mut pixels = Vec::new();
+            r.by_ref().take(len).read_to_end(&mut pixels)?;
+            if pixels.len() as u64 != len {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+            animation.frames.push(Frame { duration_ms, pixels });
+        }
+        Ok(animation)
+    }
+
+    /// Write the animation in the current version of the format.
+    pub fn write(&self, mut w: impl Write) -> Result<(), Error> {
+        let name = self.name.as_deref().unwrap_or_default().as_bytes();
+        if name.len() > u8::MAX as usize {
+            return Err(Error::InvalidName);
+        }
+        if let Some((index, frame)) = self.frames.iter().enumerate().find(|(_, f)| f.pixels.len() != self.frame_len()) {
+            return Err(Error::FrameSize { index, len: frame.pixels.len() });
+        }
+        w.write_all(&MAGIC)?;
+        w.write_all(&[VERSION, self.format.id()])?;
+        w.write_all(&self.width.to_be_bytes())?;
+        w.write_all(&self.height.to_be_bytes())?;
+        w.write_all(&(self.frames.len() as u32).to_be_bytes())?;
+        w.write_all(&self.loop_count.to_be_bytes())?;
+        w.write_all(&[name.len() as u8])?;
+        w.write_all(name)?;
+        for frame in self.frames.iter() {
+            w.write_all(&frame.duration_ms.to_be_bytes())?;
+            w.write_all(&frame.pixels)?;
+        }
+        Ok(())
+    }
+
+    /// Decode the frames of a SE203 stream, showing every one for
+    /// `duration_ms`. Malformed frames are skipped.
+    pub fn from_se203(stream: &[u8], framing: Framing, checksum: Checksum, duration_ms: u16) -> Self {
+        let mut decoder = Decoder::with_checksum(framing, checksum);
+        let mut images = Vec::new();
+        for &b in stream {
+            if let Ok(Some(Packet::Image(image))) = decoder.push(b) {
+                images.push(*image);
+            }
+        }
+        Animation::from_images(&images, duration_ms)
+    }
+
+    /// Return the frames as images, if the animation is 8×8 RGB.
+    pub fn images(&self) -> Result<Vec<Image>, Error> {
+        if (self.width, self.height, self.format) != (8, 8, PixelFormat::Rgb888) {
+            return Err(Error::NotSe203);
+        }
+        let mut images = Vec::new();
+        for (index, frame) in self.frames.iter().enumerate() {
+            let mut image = Image::default();
+            if frame.pixels.len() != protocol::FRAME_LEN {
+                return Err(Error::FrameSize { index, len: frame.pixels.len() });
+            }
+            image.as_mut().copy_from_slice(&frame.pixels);
+            images.push(image);
+        }
+        Ok(images)
+    }
+
+    /// Encode the frames, once and without timing, as a SE203 stream.
+    pub fn to_se203(&self, framing: Framing, checksum: Checksum) -> Result<Vec<u8>, Error> {
+        let mut stream = Vec::new();
+        for image in self.images()? {
+            protocol::encode_with_checksum(&image, framing, checksum, |b| stream.push(b));
+        }
+        Ok(stream)
+    }
+}
@@ -0,0 +1,89 @@
+//! Convert PNG, animated GIF and PPM files into a SE203 frame file.
+//!
+//! Every input frame is resized to 8×8 and written as a legacy SE203 frame,
+//! so pixel bytes are clamped to 0xfe. The display duration of every frame,
+//! in milliseconds, is written one per line next to the output with the
+//! `.delays` extension.
+//!
+//! With `--gamma`, every byte goes through `gamma::gamma_uncorrect()`, the
+//! inverse of the curve the matrix driver applies when displaying, so that
+//! the two cancel out and the LEDs get the source values themselves. Only use
+//! it for content whose values are already linear LED intensities: ordinary
+//! images are gamma encoded and look right without it.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+use clap::{value_parser, Arg, ArgAction, Command};
+use tp_led_matrix::convert::{self, Filter};
+use tp_led_matrix::gamma;
+use tp_led_matrix::protocol::{self, Framing};
+
+fn main() -> io::Result<()> {
+    let matches = Command::new("led-convert")
+        .about("Convert PNG, GIF and PPM files into a SE203 frame file")
+        .arg(Arg::new("INPUT")
+            .help("Image files, converted in order")
+            .required(true)
+            .multiple_values(true)
+            .value_parser(value_parser!(PathBuf)))
+        .arg(Arg::new("output")
+            .short('o')
+            .long("output")
+            .help("Frame file to write")
+            .takes_value(true)
+            .required(true)
+            .value_parser(value_parser!(PathBuf)))
+        .arg(Arg::new("filter")
+            .long("filter")
+            .help("How pixels are combined when resizing")
+            .value_parser(["nearest", "box", "bilinear"])
+            .default_value("box"))
+        .arg(Arg::new("gamma")
+            .short('g')
+            .long("gamma")
+            .help("Apply the inverse of the gamma curve of the matrix driver, for content in linear intensities")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("delay")
+            .short('d')
+            .long("delay")
+            .help("Milliseconds to display frames whose file has no delay")
+            .value_parser(value_parser!(u32))
+            .default_value("100"))
+        .get_matches();
+
+    let filter = match matches.get_one::<String>("filter").unwrap().as_str() {
+        "nearest" => Filter::Nearest,
+        "bilinear" => Filter::Bilinear,
+        _ => Filter::Box,
+    };
+    let precompensate = matches.get_flag("gamma");
+    let default_delay = *matches.get_one::<u32>("delay").unwrap();
+    let output = matches.get_one::<PathBuf>("output").unwrap();
+
+    let mut frames = BufWriter::new(File::create(output)?);
+    let mut delays = BufWriter::new(File::create(output.with_extension("delays"))?);
+    let mut count = 0;
+    for path in matches.get_many::<PathBuf>("INPUT").unwrap() {
+        let decoded = convert::load(path)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+        for frame in decoded {
+            let mut image = convert::resize(&frame.image, filter);
+            if precompensate {
+                for byte in image.as_mut().iter_mut() {
+                    *byte = gamma::gamma_uncorrect(*byte);
+                }
+            }
+            let mut bytes = Vec::new();
+            protocol::encode(&image, Framing::Legacy, |b| bytes.push(b));
+            frames.write_all(&bytes)?;
+            writeln!(delays, "{}", frame.delay_ms.unwrap_or(default_delay))?;
+            count += 1;
+        }
+    }
+    frames.flush()?;
+    delays.flush()?;
+    eprintln!("{} frames written to {}", count, output.display());
+    Ok(())
+}
@@ -0,0 +1,256 @@
+//! Stream SE203 frames to the board at a fixed frame rate.
+//!
+//! Frames are read from legacy SE203 files such as `many_frames.bin`, from
+//! PNG, GIF and PPM images resized to 8×8 like `led-convert` does, or from
+//! every such file of a directory in name order, then sent again with the
+//! chosen framing. With `--ack`, every frame waits for the `ACK` of the firmware
+//! and is sent again after a `NACK` or a timeout.
+//!
+//! With `--hdr`, frames are sent with 12 bits per channel, for the dithered
+//! display mode: images keep the precision gained by resizing them, frames
+//! of SE203 files are only widened.
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use clap::{value_parser, Arg, ArgAction, Command};
+use tp_led_matrix::command::{Reply, ReplyDecoder};
+use tp_led_matrix::convert::{self, Filter};
+use tp_led_matrix::hdr::HdrImage;
+use tp_led_matrix::protocol::{self, Checksum, Decoder, Framing, Packet};
+use tp_led_matrix::tty::{self, Pty};
+
+/// Read the frames of a legacy SE203 file or of an image file, or of every
+/// file of a directory. Images are resized with 12 bits per channel if
+/// `hdr` is set, with 8 bits otherwise.
+fn load(path: &Path, hdr: bool, frames: &mut Vec<HdrImage>) -> io::Result<()> {
+    if path.is_dir() {
+        let mut entries = fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<Vec<PathBuf>>>()?;
+        entries.sort();
+        for entry in entries.iter().filter(|entry| entry.is_file()) {
+            load(entry, hdr, frames)?;
+        }
+        return Ok(());
+    }
+    let data = fs::read(path)?;
+    // SE203 files start with a sync byte, which no image format does
+    if data.first() != Some(&protocol::SYNC) {
+        let decoded = convert::read(&data)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+        frames.extend(decoded.iter().map(|frame| if hdr {
+            convert::resize_hdr(&frame.image, Filter::Box)
+        } else {
+            HdrImage::from(&convert::resize(&frame.image, Filter::Box))
+        }));
+        return Ok(());
+    }
+    let mut decoder = Decoder::new();
+    for b in data {
+        if let Ok(Some(Packet::Image(image))) = decoder.push(b) {
+            frames.push(HdrImage::from(image));
+        }
+    }
+    Ok(())
+}
+
+/// Wait for the reply to a frame, ignoring other bytes and skipping whole
+/// statuses, whose bytes may equal `ACK` or `NACK`. Return whether the frame
+/// was acknowledged, or `None` after a timeout.
+fn wait_reply(port: &mut File, replies: &mut ReplyDecoder, timeout: Duration) -> io::Result<Option<bool>> {
+    let deadline = Instant::now() + timeout;
+    while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+        let b = match tty::read_byte_timeout(port, left)? {
+            Some(b) => b,
+            None => break,
+        };
+        match replies.push(b) {
+            Some(Reply::Ack) => return Ok(Some(true)),
+            Some(Reply::Nack) => return Ok(Some(false)),
+            _ => {}
+        }
+    }
+    Ok(None)
+}
+
+/// Counters displayed while streaming.
+#[derive(Default)]
+struct Progress {
+    sent: u64,
+    nacks: u64,
+    timeouts: u64,
+    lost: u64,
+}
+
+fn main() -> io::Result<()> {
+    let matches = Command::new("led-send")
+        .about("Stream SE203 frames to the board at a fixed frame rate")
+        .arg(Arg::new("device")
+            .short('d')
+            .long("device")
+            .help("Serial device of the board")
+            .default_value("/dev/ttyACM0"))
+        .arg(Arg::new("FRAMES")
+            .help("SE203 frame files, PNG, GIF or PPM images, or directories of them")
+            .required(true)
+            .multiple_values(true)
+            .value_parser(value_parser!(PathBuf)))
+        .arg(Arg::new("pty")
+            .short('p')
+            .long("pty")
+            .help("Send the frames to a new pseudo-terminal instead of the device")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("framing")
+            .long("framing")
+            .help("Framing expected by the firmware: legacy SE203 or byte-stuffed SE203 v2")
+            .value_parser(["legacy", "stuffed"])
+            .default_value("legacy"))
+        .arg(Arg::new("crc")
+            .long("crc")
+            .help("Append a CRC-16 to every frame, with the stuffed framing")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("hdr")
+            .long("hdr")
+            .help("Send frames with 12 bits per channel, with the stuffed framing")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("ack")
+            .long("ack")
+            .help("Wait for the ACK of every frame and retransmit it on NACK, with --crc")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("retries")
+            .long("retries")
+            .help("Maximal number of retransmissions of a frame with --ack")
+            .value_parser(value_parser!(u32))
+            .default_value("3"))
+        .arg(Arg::new("timeout")
+            .long("timeout")
+            .help("Milliseconds to wait for the reply to a frame with --ack")
+            .value_parser(value_parser!(u64).range(1..))
+            .default_value("200"))
+        .arg(Arg::new("fps")
+            .short('f')
+            .long("fps")
+            .help("Number of frames sent per second")
+            .value_parser(value_parser!(u32).range(1..))
+            .default_value("60"))
+        .arg(Arg::new("loop")
+            .short('l')
+            .long("loop")
+            .help("Send the frames again and again until interrupted")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("quiet")
+            .short('q')
+            .long("quiet")
+            .help("Do not display the progress")
+            .action(ArgAction::SetTrue))
+        .get_matches();
+
+    let framing = match matches.get_one::<String>("framing").unwrap().as_str() {
+        "stuffed" => Framing::Stuffed,
+        _ => Framing::Legacy,
+    };
+    let checksum = if matches.get_flag("crc") { Checksum::Crc16 } else { Checksum::None };
+    if checksum != Checksum::None && framing != Framing::Stuffed {
+        eprintln!("--crc requires --framing stuffed");
+        std::process::exit(2);
+    }
+    let hdr = matches.get_flag("hdr");
+    if hdr && framing != Framing::Stuffed {
+        eprintln!("--hdr requires --framing stuffed");
+        std::process::exit(2);
+    }
+    let ack = matches.get_flag("ack");
+    if ack && checksum == Checksum::None {
+        eprintln!("--ack requires --crc");
+        std::process::exit(2);
+    }
+    let retries = *matches.get_one::<u32>("retries").unwrap();
+    let timeout = Duration::from_millis(*matches.get_one::<u64>("timeout").unwrap());
+    let period = Duration::from_secs(1) / *matches.get_one::<u32>("fps").unwrap();
+    let repeat = matches.get_flag("loop");
+    let quiet = matches.get_flag("quiet");
+
+    let mut frames = Vec::new();
+    for path in matches.get_many::<PathBuf>("FRAMES").unwrap() {
+        load(path, hdr, &mut frames)?;
+    }
+    if frames.is_empty() {
+        eprintln!("No frame found");
+        std::process::exit(1);
+    }
+
+    // The pseudo-terminal must stay open while sending
+    let mut pty = None;
+    let mut port = if matches.get_flag("pty") {
+        let opened = Pty::open()?;
+        eprintln!("Sending frames to {}", opened.path.display());
+        let port = opened.master.try_clone()?;
+        pty = Some(opened);
+        port
+    } else {
+        tty::open_serial(Path::new(matches.get_one::<String>("device").unwrap()))?
+    };
+
+    let mut progress = Progress::default();
+    let mut replies = ReplyDecoder::new();
+    let mut buffer = Vec::new();
+    let start = Instant::now();
+    let mut next_frame = start;
+    loop {
+        for (index, image) in frames.iter().enumerate() {
+            buffer.clear();
+            // 8-bit frames are widened exactly, so they are sent unchanged
+            if hdr {
+                protocol::encode_hdr(image, checksum, |b| buffer.push(b));
+            } else {
+                protocol::encode_with_checksum(&image.to_image(), framing, checksum, |b| buffer.push(b));
+            }
+            let mut attempts = 0;
+            loop {
+                port.write_all(&buffer)?;
+                attempts += 1;
+                if !ack {
+                    break;
+                }
+                match wait_reply(&mut port, &mut replies, timeout)? {
+                    Some(true) => break,
+                    Some(false) => progress.nacks += 1,
+                    None => progress.timeouts += 1,
+                }
+                if attempts > retries {
+                    progress.lost += 1;
+                    break;
+                }
+            }
+            progress.sent += 1;
+            if !quiet {
+                let fps = progress.sent as f64 / start.elapsed().as_secs_f64();
+                eprint!("\r{}/{} frames, {:.1} fps", index + 1, frames.len(), fps);
+                if ack {
+                    eprint!(", {} NACK, {} timeouts, {} lost", progress.nacks, progress.timeouts, progress.lost);
+                }
+            }
+            next_frame += period;
+            match next_frame.checked_duration_since(Instant::now()) {
+                Some(wait) => thread::sleep(wait),
+                None => next_frame = Instant::now(),
+            }
+        }
+        if !repeat {
+            break;
+        }
+    }
+    if !quiet {
+        eprintln!();
+    }
+    drop(pty);
+    if progress.lost > 0 {
+        eprintln!("{} frames were not acknowledged", progress.lost);
+        std::process::exit(1);
+    }
+    Ok(())
+}
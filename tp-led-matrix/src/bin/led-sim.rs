@@ -0,0 +1,163 @@
+//! Render a SE203 frame stream in a truecolor terminal.
+//!
+//! The stream can come from a file, from stdin, or from a new pseudo-terminal
+//! so that tools writing to a serial port can be pointed at the simulator.
+//! With `--crc`, frames received on the pseudo-terminal are acknowledged like
+//! the firmware does. With `--text`, a string is scrolled instead.
+//! 12-bit frames are rendered with their 8 most significant bits.
+
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use clap::{value_parser, Arg, ArgAction, Command};
+use tp_led_matrix::font::Marquee;
+use tp_led_matrix::image::WHITE;
+use tp_led_matrix::protocol::{self, Checksum, Decoder, Error, Framing, Packet};
+use tp_led_matrix::tty::Pty;
+use tp_led_matrix::{Color, Image};
+
+/// Draw an image as 8 lines of 8 double-width cells, from the top-left corner.
+fn draw(out: &mut impl Write, image: &Image, gamma: bool) -> io::Result<()> {
+    write!(out, "\x1b[H")?;
+    for row in 0..8 {
+        for pixel in image.row(row) {
+            let pixel = if gamma { pixel.gamma_correct() } else { *pixel };
+            write!(out, "\x1b[48;2;{};{};{}m  ", pixel.r, pixel.g, pixel.b)?;
+        }
+        writeln!(out, "\x1b[0m")?;
+    }
+    out.flush()
+}
+
+/// Scroll `text` once, one column every `period`.
+fn scroll(text: &str, color: Color, period: Duration, gamma: bool) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    write!(out, "\x1b[2J\x1b[?25l")?;
+    for image in Marquee::new(text, color, Color::default()) {
+        draw(&mut out, &image, gamma)?;
+        thread::sleep(period);
+    }
+    write!(out, "\x1b[?25h")?;
+    out.flush()
+}
+
+fn main() -> io::Result<()> {
+    let matches = Command::new("led-sim")
+        .about("Render a SE203 frame stream in a truecolor terminal")
+        .arg(Arg::new("INPUT")
+            .help("File to read the frames from, or - for stdin")
+            .default_value("-"))
+        .arg(Arg::new("pty")
+            .short('p')
+            .long("pty")
+            .help("Read the frames from a new pseudo-terminal instead of INPUT")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("gamma")
+            .short('g')
+            .long("gamma")
+            .help("Apply the gamma correction done by the matrix driver")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("framing")
+            .long("framing")
+            .help("Framing of the stream: legacy SE203 or byte-stuffed SE203 v2")
+            .value_parser(["legacy", "stuffed"])
+            .default_value("legacy"))
+        .arg(Arg::new("crc")
+            .long("crc")
+            .help("Expect a CRC-16 after every frame, with the stuffed framing")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("fps")
+            .short('f')
+            .long("fps")
+            .help("Maximal number of frames rendered per second")
+            .value_parser(value_parser!(u32).range(1..))
+            .default_value("60"))
+        .arg(Arg::new("text")
+            .short('t')
+            .long("text")
+            .help("Scroll TEXT with the built-in font instead of reading frames")
+            .value_name("TEXT")
+            .takes_value(true))
+        .arg(Arg::new("color")
+            .long("color")
+            .help("Color of the text, as #rrggbb or a name")
+            .value_parser(|s: &str| s.parse::<Color>().map_err(|e| e.to_string()))
+            .default_value("white"))
+        .arg(Arg::new("speed")
+            .long("speed")
+            .help("Columns scrolled per second")
+            .value_parser(value_parser!(u32).range(1..))
+            .default_value("10"))
+        .get_matches();
+
+    let gamma = matches.get_flag("gamma");
+    if let Some(text) = matches.get_one::<String>("text") {
+        let color = matches.get_one::<Color>("color").copied().unwrap_or(WHITE);
+        let period = Duration::from_secs(1) / *matches.get_one::<u32>("speed").unwrap();
+        return scroll(text, color, period, gamma);
+    }
+    let framing = match matches.get_one::<String>("framing").unwrap().as_str() {
+        "stuffed" => Framing::Stuffed,
+        _ => Framing::Legacy,
+    };
+    let checksum = if matches.get_flag("crc") { Checksum::Crc16 } else { Checksum::None };
+    if checksum != Checksum::None && framing != Framing::Stuffed {
+        eprintln!("--crc requires --framing stuffed");
+        std::process::exit(2);
+    }
+    let period = Duration::from_secs(1) / *matches.get_one::<u32>("fps").unwrap();
+    // Where to send ACK and NACK, if anywhere
+    let mut replies = None;
+    let input: Box<dyn Read> = if matches.get_flag("pty") {
+        let pty = Pty::open()?;
+        eprintln!("Waiting for frames on {}", pty.path.display());
+        if checksum != Checksum::None {
+            replies = Some(pty.master.try_clone()?);
+        }
+        Box::new(pty)
+    } else {
+        match matches.get_one::<String>("INPUT").unwrap().as_str() {
+            "-" => Box::new(io::stdin()),
+            path => Box::new(File::open(path)?),
+        }
+    };
+
+    let mut out = io::stdout().lock();
+    write!(out, "\x1b[2J\x1b[?25l")?;
+    let mut decoder = Decoder::with_checksum(framing, checksum);
+    let mut next_frame = Instant::now();
+    for b in BufReader::new(input).bytes() {
+        match decoder.push(b?) {
+            Ok(Some(packet @ (Packet::Image(_) | Packet::Hdr(_)))) => {
+                if let Some(replies) = replies.as_mut() {
+                    replies.write_all(&[protocol::ACK])?;
+                }
+                let image = match packet {
+                    Packet::Hdr(hdr) => hdr.to_image(),
+                    Packet::Image(image) => *image,
+                    Packet::Command(_) => unreachable!(),
+                };
+                draw(&mut out, &image, gamma)?;
+                next_frame += period;
+                match next_frame.checked_duration_since(Instant::now()) {
+                    Some(wait) => thread::sleep(wait),
+                    None => next_frame = Instant::now(),
+                }
+            }
+            Ok(None) | Ok(Some(Packet::Command(_))) | Err(Error::Overrun) => {}
+            Err(_) => {
+                if let Some(replies) = replies.as_mut() {
+                    replies.write_all(&[protocol::NACK])?;
+                }
+            }
+        }
+    }
+    write!(out, "\x1b[?25h")?;
+    out.flush()?;
+    let stats = decoder.stats();
+    eprintln!("{} frames, {} overruns, {} short frames, {} bytes dropped, {} invalid escapes, {} checksum errors",
+        stats.frames, stats.overruns, stats.short_frames, stats.dropped, stats.invalid_escapes, stats.checksum_errors);
+    Ok(())
+}
@@ -0,0 +1,120 @@
+//! Mixing of colors and images: blend modes with an opacity, and colors with
+//! an alpha channel composited over others.
+
+use crate::image;
+use crate::{Color, Image};
+
+/// How a color is combined with the color below it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BlendMode {
+    /// The color replaces the one below.
+    Normal,
+    /// The channels are added, saturating at 255.
+    Additive,
+    /// The channels are multiplied, which can only darken.
+    Multiply,
+    /// The inverted channels are multiplied, which can only lighten.
+    Screen,
+}
+
+impl BlendMode {
+    /// Combine `top` with `bottom`, before applying any opacity.
+    pub fn apply(&self, bottom: Color, top: Color) -> Color {
+        match self {
+            BlendMode::Normal => top,
+            BlendMode::Additive => bottom + top,
+            BlendMode::Multiply => bottom * top,
+            BlendMode::Screen => image::WHITE - (image::WHITE - bottom) * (image::WHITE - top),
+        }
+    }
+}
+
+impl Color {
+    /// Blend `top` over this color with `mode`, then mix the result with
+    /// this color by `opacity`, limited to [0, 1].
+    pub fn blend(self, top: Color, mode: BlendMode, opacity: f32) -> Color {
+        Color::lerp(self, mode.apply(self, top), opacity)
+    }
+}
+
+/// Color with an alpha channel, from transparent at 0 to opaque at 255.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RgbaColor {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl From<Color> for RgbaColor {
+    /// Make an opaque color.
+    fn from(color: Color) -> Self {
+        RgbaColor::new(color, 255)
+    }
+}
+
+/// Divide by 255, rounding to the nearest.
+fn div255(x: u32) -> u32 {
+    (x + 127) / 255
+}
+
+impl RgbaColor {
+    /// Create a color from its RGB channels and its alpha.
+    pub const fn new(color: Color, a: u8) -> Self {
+        RgbaColor { r: color.r, g: color.g, b: color.b, a }
+    }
+
+    /// Returns the RGB channels, ignoring the alpha.
+    pub const fn color(&self) -> Color {
+        Color { r: self.r, g: self.g, b: self.b }
+    }
+
+    /// Composite this color over an opaque color.
+    pub fn over_color(self, below: Color) -> Color {
+        let a = self.a as u32;
+        let f = |top: u8, bottom: u8| div255(top as u32 * a + bottom as u32 * (255 - a)) as u8;
+        Color { r: f(self.r, below.r), g: f(self.g, below.g), b: f(self.b, below.b) }
+    }
+
+    /// Composite this color over another one with the Porter-Duff "over"
+    /// operator. The channels are not premultiplied.
+    pub fn over(self, below: RgbaColor) -> RgbaColor {
+        let top = self.a as u32 * 255;
+        let bottom = below.a as u32 * (255 - self.a as u32);
+        let a = top + bottom;
+        if a == 0 {
+            return RgbaColor::default();
+        }
+        let f = |t: u8, b: u8| ((t as u32 * top + b as u32 * bottom + a / 2) / a) as u8;
+        RgbaColor {
+            r: f(self.r, below.r),
+            g: f(self.g, below.g),
+            b: f(self.b, below.b),
+            a: div255(a) as u8,
+        }
+    }
+}
+
+impl<const W: usize, const H: usize> Image<W, H> {
+    /// Returns this image with `top` blended over it pixel by pixel, as
+    /// `Color::blend()` does.
+    pub fn blend(&self, top: &Image<W, H>, mode: BlendMode, opacity: f32) -> Image<W, H> {
+        let mut image = *self;
+        for (pixel, top) in image.pixels_mut().iter_mut().zip(top.pixels()) {
+            *pixel = pixel.blend(*top, mode, opacity);
+        }
+        image
+    }
+
+    /// Returns this image with the pixels of `overlay`, row by row,
+    /// composited over it. Pixels missing from `overlay` are left as is.
+    pub fn composite(&self, overlay: &[RgbaColor]) -> Image<W, H> {
+        let mut image = *self;
+        for (pixel, top) in image.pixels_mut().iter_mut().zip(overlay.iter()) {
+            *pixel = top.over_color(*pixel);
+        }
+        image
+    }
+}
@@ -0,0 +1,195 @@
+//! Conversions of `Color` from and to HSV, HSL, hexadecimal values and
+//! color names.
+//!
+//! Hues are in degrees and wrap around, saturation, value and lightness are
+//! between 0 and 1. Parsing does not allocate, so it also works in `no_std`.
+
+use core::fmt;
+use core::str::FromStr;
+
+#[cfg(not(feature = "std"))]
+use micromath::F32Ext;
+
+use crate::image::{self, Color};
+
+/// Color as hue, saturation and value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Hsv {
+    pub h: f32,
+    pub s: f32,
+    pub v: f32,
+}
+
+/// Color as hue, saturation and lightness.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Hsl {
+    pub h: f32,
+    pub s: f32,
+    pub l: f32,
+}
+
+/// Invalid color string.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ParseColorError {
+    /// A hexadecimal color does not have 6 digits.
+    InvalidLength(usize),
+    /// A hexadecimal color contains a character which is not a digit.
+    InvalidDigit,
+    /// The string is neither a hexadecimal color nor a known name.
+    UnknownName,
+}
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseColorError::InvalidLength(len) => write!(f, "expected 6 hexadecimal digits, got {}", len),
+            ParseColorError::InvalidDigit => write!(f, "invalid hexadecimal digit"),
+            ParseColorError::UnknownName => write!(f, "unknown color name"),
+        }
+    }
+}
+
+/// Colors known by `Color::named()`, with their lowercase names.
+pub const NAMED: [(&str, Color); 16] = [
+    ("black", image::BLACK),
+    ("white", image::WHITE),
+    ("red", image::RED),
+    ("green", image::GREEN),
+    ("blue", image::BLUE),
+    ("yellow", image::YELLOW),
+    ("cyan", image::CYAN),
+    ("magenta", image::MAGENTA),
+    ("orange", image::ORANGE),
+    ("purple", image::PURPLE),
+    ("pink", image::PINK),
+    ("gray", image::GRAY),
+    ("grey", image::GRAY),
+    ("brown", image::BROWN),
+    ("teal", image::TEAL),
+    ("navy", image::NAVY),
+];
+
+/// Bring a hue into [0, 360).
+fn wrap_hue(h: f32) -> f32 {
+    let h = h - 360.0 * (h / 360.0).floor();
+    if h >= 360.0 { 0.0 } else { h }
+}
+
+/// Scale a channel between 0 and 1 to a rounded 8-bit value.
+fn to_u8(x: f32) -> u8 {
+    (x * 255.0).round().clamp(0.0, 255.0) as u8
+}
+
+/// Build a color from its hue, its chroma `c` and the value `m` added to
+/// every channel.
+fn from_chroma(h: f32, c: f32, m: f32) -> Color {
+    let h = wrap_hue(h) / 60.0;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u8 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    Color { r: to_u8(r + m), g: to_u8(g + m), b: to_u8(b + m) }
+}
+
+impl Color {
+    /// Create a color from a `0xRRGGBB` value. Higher bits are ignored.
+    pub const fn from_hex(hex: u32) -> Self {
+        Color { r: (hex >> 16) as u8, g: (hex >> 8) as u8, b: hex as u8 }
+    }
+
+    /// Returns the color as a `0xRRGGBB` value.
+    pub const fn to_hex(&self) -> u32 {
+        (self.r as u32) << 16 | (self.g as u32) << 8 | self.b as u32
+    }
+
+    /// Returns the color with the given name from `NAMED`, ignoring case.
+    pub fn named(name: &str) -> Option<Self> {
+        NAMED.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|&(_, color)| color)
+    }
+
+    /// Create a color from its HSV representation. Saturation and value are
+    /// limited to [0, 1].
+    pub fn from_hsv(hsv: Hsv) -> Self {
+        let (s, v) = (hsv.s.clamp(0.0, 1.0), hsv.v.clamp(0.0, 1.0));
+        let c = v * s;
+        from_chroma(hsv.h, c, v - c)
+    }
+
+    /// Returns the HSV representation of the color. The hue of grays is 0.
+    pub fn to_hsv(&self) -> Hsv {
+        let (h, max, min) = self.hue();
+        let s = if max > 0.0 { (max - min) / max } else { 0.0 };
+        Hsv { h, s, v: max }
+    }
+
+    /// Create a color from its HSL representation. Saturation and lightness
+    /// are limited to [0, 1].
+    pub fn from_hsl(hsl: Hsl) -> Self {
+        let (s, l) = (hsl.s.clamp(0.0, 1.0), hsl.l.clamp(0.0, 1.0));
+        let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        from_chroma(hsl.h, c, l - c / 2.0)
+    }
+
+    /// Returns the HSL representation of the color. The hue of grays is 0.
+    pub fn to_hsl(&self) -> Hsl {
+        let (h, max, min) = self.hue();
+        let l = (max + min) / 2.0;
+        let s = if max > min { (max - min) / (1.0 - (2.0 * l - 1.0).abs()) } else { 0.0 };
+        Hsl { h, s, l }
+    }
+
+    /// Returns the hue, and the largest and smallest channels between 0 and 1.
+    fn hue(&self) -> (f32, f32, f32) {
+        let (r, g, b) = (self.r as f32 / 255.0, self.g as f32 / 255.0, self.b as f32 / 255.0);
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+        let h = if delta == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / delta)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        (wrap_hue(h), max, min)
+    }
+}
+
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    /// Parse `#rrggbb`, `0xrrggbb` or a name from `NAMED`. Digits may be
+    /// uppercase.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix('#').or_else(|| s.strip_prefix("0x")).or_else(|| s.strip_prefix("0X")) {
+            None => Color::named(s).ok_or(ParseColorError::UnknownName),
+            Some(digits) if digits.len() != 6 => Err(ParseColorError::InvalidLength(digits.len())),
+            Some(digits) if !digits.bytes().all(|b| b.is_ascii_hexdigit()) => Err(ParseColorError::InvalidDigit),
+            Some(digits) => u32::from_str_radix(digits, 16).map(Color::from_hex).map_err(|_| ParseColorError::InvalidDigit),
+        }
+    }
+}
+
+impl fmt::LowerHex for Color {
+    /// Format the color as `rrggbb`, so that `{:x}` after a `#` gives a CSS
+    /// color.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+impl fmt::UpperHex for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02X}{:02X}{:02X}", self.r, self.g, self.b)
+    }
+}
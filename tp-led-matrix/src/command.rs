@@ -0,0 +1,357 @@
+//! Commands sent on the serial link alongside the images.
+//!
+//! Commands need the stuffed framing, which the firmware expects when it is
+//! built with the `se203-v2` feature. A command is sent as the
+//! `protocol::CMD` byte followed by its opcode and arguments, stuffed like
+//! pixel bytes:
+//!
+//! | Opcode | Command           | Arguments                    |
+//! |--------|-------------------|------------------------------|
+//! | 0x01   | `SetBrightness`   | brightness                   |
+//! | 0x02   | `SetGamma`        | `gamma::Preset` id           |
+//! | 0x03   | `Fill`            | red, green, blue             |
+//! | 0x04   | `Gradient`        | red, green, blue             |
+//! | 0x05   | `Pause`           |                              |
+//! | 0x06   | `Resume`          |                              |
+//! | 0x07   | `QueryStatus`     |                              |
+//! | 0x08   | `Reset`           |                              |
+//! | 0x09   | `SetWhiteBalance` | red, green, blue, at most 63 |
+//! | 0x0a   | `SetDisplayMode`  | `DisplayMode` id             |
+//!
+//! The opcode `protocol::HDR_FRAME` (0x0b) is reserved for 12-bit frames,
+//! which `protocol::Decoder` receives instead of a command.
+//!
+//! The answer to `QueryStatus` is sent back unstuffed as `CMD`, followed by
+//! the `STATUS_LEN` bytes of `Status::to_bytes()`. These bytes may equal
+//! `protocol::ACK` or `protocol::NACK`, so a host reading replies must skip
+//! a whole status after `CMD` before looking for them, as `ReplyDecoder`
+//! does.
+
+use crate::dot_correction::WhiteBalance;
+use crate::gamma::Preset;
+use crate::protocol::{ACK, CMD, NACK};
+use crate::Color;
+
+/// Request to change the display or to report on it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Command {
+    /// Scale the output current by `brightness`/255.
+    SetBrightness(u8),
+    /// Select the gamma correction applied by the driver.
+    SetGamma(Preset),
+    /// Display an image of one color.
+    Fill(Color),
+    /// Display `Image::gradient()` of a color.
+    Gradient(Color),
+    /// Keep the current image until `Resume`, ignoring new ones: images
+    /// received meanwhile are dropped, although frames are still
+    /// acknowledged, and the next one received after `Resume` is shown.
+    Pause,
+    Resume,
+    /// Send the `Status` back.
+    QueryStatus,
+    /// Restart the board.
+    Reset,
+    /// Calibrate the colors of the LEDs.
+    SetWhiteBalance(WhiteBalance),
+    /// Select how images are rendered.
+    SetDisplayMode(DisplayMode),
+}
+
+/// How the display task renders images.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DisplayMode {
+    /// Every row is latched once per frame with 8 bits per channel.
+    Standard,
+    /// Frames are refreshed faster and dithered over `hdr::SUBFRAMES`
+    /// frames, for 12 bits of output precision per channel.
+    Dithered,
+}
+
+impl DisplayMode {
+    /// Returns the identifier of the mode in commands.
+    pub fn id(&self) -> u8 {
+        match self {
+            DisplayMode::Standard => 0,
+            DisplayMode::Dithered => 1,
+        }
+    }
+
+    /// Returns the mode with the given identifier, if any.
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(DisplayMode::Standard),
+            1 => Some(DisplayMode::Dithered),
+            _ => None,
+        }
+    }
+}
+
+/// Invalid command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    UnknownOpcode(u8),
+    /// The argument is out of range for the opcode.
+    InvalidArgument { opcode: u8, value: u8 },
+}
+
+impl Command {
+    /// Returns the number of argument bytes following `opcode`.
+    fn args_len(opcode: u8) -> Result<usize, Error> {
+        match opcode {
+            0x01 | 0x02 | 0x0a => Ok(1),
+            0x03 | 0x04 | 0x09 => Ok(3),
+            0x05..=0x08 => Ok(0),
+            _ => Err(Error::UnknownOpcode(opcode)),
+        }
+    }
+
+    fn from_bytes(opcode: u8, args: &[u8]) -> Result<Self, Error> {
+        let color = || Color { r: args[0], g: args[1], b: args[2] };
+        match opcode {
+            0x01 => Ok(Command::SetBrightness(args[0])),
+            0x02 => Preset::from_id(args[0])
+                .map(Command::SetGamma)
+                .ok_or(Error::InvalidArgument { opcode, value: args[0] }),
+            0x03 => Ok(Command::Fill(color())),
+            0x04 => Ok(Command::Gradient(color())),
+            0x05 => Ok(Command::Pause),
+            0x06 => Ok(Command::Resume),
+            0x07 => Ok(Command::QueryStatus),
+            0x08 => Ok(Command::Reset),
+            0x09 => WhiteBalance::new(args[0], args[1], args[2]).map(Command::SetWhiteBalance).ok_or(
+                Error::InvalidArgument { opcode, value: args[0].max(args[1]).max(args[2]) },
+            ),
+            0x0a => DisplayMode::from_id(args[0])
+                .map(Command::SetDisplayMode)
+                .ok_or(Error::InvalidArgument { opcode, value: args[0] }),
+            _ => Err(Error::UnknownOpcode(opcode)),
+        }
+    }
+
+    /// Send the opcode and arguments of the command, unstuffed, to `out`.
+    pub fn write(&self, mut out: impl FnMut(u8)) {
+        match *self {
+            Command::SetBrightness(brightness) => {
+                out(0x01);
+                out(brightness);
+            }
+            Command::SetGamma(preset) => {
+                out(0x02);
+                out(preset.id());
+            }
+            Command::Fill(color) => {
+                out(0x03);
+                out(color.r);
+                out(color.g);
+                out(color.b);
+            }
+            Command::Gradient(color) => {
+                out(0x04);
+                out(color.r);
+                out(color.g);
+                out(color.b);
+            }
+            Command::Pause => out(0x05),
+            Command::Resume => out(0x06),
+            Command::QueryStatus => out(0x07),
+            Command::Reset => out(0x08),
+            Command::SetWhiteBalance(white_balance) => {
+                out(0x09);
+                out(white_balance.red);
+                out(white_balance.green);
+                out(white_balance.blue);
+            }
+            Command::SetDisplayMode(mode) => {
+                out(0x0a);
+                out(mode.id());
+            }
+        }
+    }
+}
+
+/// Incremental parser of the unstuffed bytes following `protocol::CMD`.
+#[derive(Default)]
+pub struct Parser {
+    opcode: Option<u8>,
+    args: [u8; 3],
+    len: usize,
+}
+
+impl Parser {
+    /// Create a parser waiting for an opcode.
+    pub fn new() -> Self {
+        Parser::default()
+    }
+
+    /// Handle one byte. Return the command once all its arguments have been
+    /// received, after which the parser waits for a new opcode.
+    pub fn push(&mut self, b: u8) -> Result<Option<Command>, Error> {
+        let opcode = match self.opcode {
+            Some(opcode) => {
+                self.args[self.len] = b;
+                self.len += 1;
+                opcode
+            }
+            None => b,
+        };
+        if self.len < Command::args_len(opcode)? {
+            self.opcode = Some(opcode);
+            return Ok(None);
+        }
+        self.opcode = None;
+        self.len = 0;
+        Command::from_bytes(opcode, &self.args).map(Some)
+    }
+}
+
+/// Display settings changed by commands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Settings {
+    pub brightness: u8,
+    pub gamma: Preset,
+    pub paused: bool,
+    pub white_balance: WhiteBalance,
+    pub mode: DisplayMode,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            brightness: 255,
+            gamma: Preset::Standard,
+            paused: false,
+            white_balance: WhiteBalance::default(),
+            mode: DisplayMode::Standard,
+        }
+    }
+}
+
+impl Settings {
+    /// Apply the commands changing the settings, and return whether `command`
+    /// was one of them.
+    pub fn apply(&mut self, command: &Command) -> bool {
+        match *command {
+            Command::SetBrightness(brightness) => self.brightness = brightness,
+            Command::SetGamma(gamma) => self.gamma = gamma,
+            Command::Pause => self.paused = true,
+            Command::Resume => self.paused = false,
+            Command::SetWhiteBalance(white_balance) => self.white_balance = white_balance,
+            Command::SetDisplayMode(mode) => self.mode = mode,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Returns whether an image received now, as a frame or by `Fill` or
+    /// `Gradient`, replaces the displayed one: it does not while paused.
+    pub fn shows_images(&self) -> bool {
+        !self.paused
+    }
+}
+
+/// Number of bytes of an encoded `Status`.
+pub const STATUS_LEN: usize = 16;
+
+/// Answer to `QueryStatus`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Status {
+    pub settings: Settings,
+    /// Frames received since the board started.
+    pub frames: u32,
+    /// Frames lost or corrupted since the board started.
+    pub errors: u32,
+}
+
+impl Status {
+    /// Encode the status as the `QueryStatus` opcode, the paused flag, the
+    /// brightness, the gamma preset id, the frame and error counters most
+    /// significant byte first, the white balance, then the display mode id.
+    pub fn to_bytes(&self) -> [u8; STATUS_LEN] {
+        let mut bytes = [0; STATUS_LEN];
+        bytes[0] = 0x07;
+        bytes[1] = self.settings.paused as u8;
+        bytes[2] = self.settings.brightness;
+        bytes[3] = self.settings.gamma.id();
+        bytes[4..8].copy_from_slice(&self.frames.to_be_bytes());
+        bytes[8..12].copy_from_slice(&self.errors.to_be_bytes());
+        let white_balance = self.settings.white_balance;
+        bytes[12..15].copy_from_slice(&[white_balance.red, white_balance.green, white_balance.blue]);
+        bytes[15] = self.settings.mode.id();
+        bytes
+    }
+
+    /// Decode a status encoded by `to_bytes()`.
+    pub fn from_bytes(bytes: &[u8; STATUS_LEN]) -> Option<Self> {
+        if bytes[0] != 0x07 || bytes[1] > 1 {
+            return None;
+        }
+        let settings = Settings {
+            paused: bytes[1] == 1,
+            brightness: bytes[2],
+            gamma: Preset::from_id(bytes[3])?,
+            white_balance: WhiteBalance::new(bytes[12], bytes[13], bytes[14])?,
+            mode: DisplayMode::from_id(bytes[15])?,
+        };
+        Some(Status {
+            settings,
+            frames: u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]),
+            errors: u32::from_be_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]),
+        })
+    }
+}
+
+/// What the firmware sends back on the serial link.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reply {
+    /// A frame was received with a valid checksum.
+    Ack,
+    /// A frame was lost or received with an invalid checksum.
+    Nack,
+    /// Answer to `QueryStatus`.
+    Status(Status),
+}
+
+/// Incremental decoder of the bytes sent back by the firmware, which does
+/// not mistake the bytes of a status for `ACK` or `NACK`.
+#[derive(Default)]
+pub struct ReplyDecoder {
+    status: [u8; STATUS_LEN],
+    /// Number of bytes of the status received so far, after `CMD`.
+    len: Option<usize>,
+}
+
+impl ReplyDecoder {
+    /// Create a decoder waiting for a reply.
+    pub fn new() -> Self {
+        ReplyDecoder::default()
+    }
+
+    /// Handle one byte. Return the reply it completes, if any. Other bytes,
+    /// and invalid statuses, are ignored.
+    pub fn push(&mut self, b: u8) -> Option<Reply> {
+        match self.len {
+            Some(len) => {
+                self.status[len] = b;
+                if len + 1 < STATUS_LEN {
+                    self.len = Some(len + 1);
+                    return None;
+                }
+                self.len = None;
+                Status::from_bytes(&self.status).map(Reply::Status)
+            }
+            None => match b {
+                CMD => {
+                    self.len = Some(0);
+                    None
+                }
+                ACK => Some(Reply::Ack),
+                NACK => Some(Reply::Nack),
+                _ => None,
+            },
+        }
+    }
+}
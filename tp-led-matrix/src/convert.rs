@@ -0,0 +1,299 @@
+//! Conversion of PNG, GIF and PPM files into 8×8 images.
+//!
+//! Files are decoded into `RgbImage`s of any size, with one `Frame` per GIF
+//! frame, then `resize()` reduces every frame to the `Image` grid, or
+//! `resize_hdr()` to an `HdrImage` keeping the precision gained by filtering.
+//! Transparent pixels are composed over black, the color of an unlit LED.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::hdr::{HdrColor, HdrImage, HDR_MAX};
+use crate::{Color, Image};
+
+/// Image of any size, row by row.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RgbImage {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Color>,
+}
+
+impl RgbImage {
+    /// Create an image from its pixels, row by row.
+    pub fn new(width: usize, height: usize, pixels: Vec<Color>) -> Self {
+        assert!(width > 0 && height > 0, "An image must not be empty");
+        assert_eq!(pixels.len(), width * height, "Wrong number of pixels");
+        RgbImage { width, height, pixels }
+    }
+
+    /// Return the pixel at column `x` of row `y`.
+    pub fn get(&self, x: usize, y: usize) -> Color {
+        self.pixels[y * self.width + x]
+    }
+}
+
+/// One decoded image and the time it stays displayed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    pub image: RgbImage,
+    /// Display duration in milliseconds, or `None` if the file has none.
+    pub delay_ms: Option<u32>,
+}
+
+/// How pixels are combined when resizing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Filter {
+    /// Take the source pixel at the center of every cell.
+    Nearest,
+    /// Average the source pixels covered by every cell.
+    Box,
+    /// Interpolate the four source pixels around the center of every cell.
+    Bilinear,
+}
+
+/// Channels of a filtered pixel, between 0 and 255 but not rounded.
+type Sample = [f64; 3];
+
+/// Reduce or enlarge `source` to the 8×8 grid.
+pub fn resize(source: &RgbImage, filter: Filter) -> Image {
+    let mut image = Image::default();
+    for row in 0..8 {
+        for col in 0..8 {
+            let [r, g, b] = sample(source, filter, col, row).map(|x| x.round() as u8);
+            image[(row, col)] = Color { r, g, b };
+        }
+    }
+    image
+}
+
+/// Reduce or enlarge `source` to the 8×8 grid with 12 bits per channel.
+pub fn resize_hdr(source: &RgbImage, filter: Filter) -> HdrImage {
+    let mut image = HdrImage::default();
+    for row in 0..8 {
+        for col in 0..8 {
+            let [r, g, b] = sample(source, filter, col, row).map(|x| (x * HDR_MAX as f64 / 255.0).round() as u16);
+            image[(row, col)] = HdrColor { r, g, b };
+        }
+    }
+    image
+}
+
+fn sample(source: &RgbImage, filter: Filter, col: usize, row: usize) -> Sample {
+    match filter {
+        Filter::Nearest => nearest(source, col, row),
+        Filter::Box => box_average(source, col, row),
+        Filter::Bilinear => bilinear(source, col, row),
+    }
+}
+
+fn nearest(source: &RgbImage, col: usize, row: usize) -> Sample {
+    let pixel = source.get((2 * col + 1) * source.width / 16, (2 * row + 1) * source.height / 16);
+    [pixel.r, pixel.g, pixel.b].map(f64::from)
+}
+
+/// Half-open range of source coordinates covered by cell `n` of 8.
+fn span(n: usize, size: usize) -> (usize, usize) {
+    let start = n * size / 8;
+    let end = ((n + 1) * size / 8).max(start + 1);
+    (start, end)
+}
+
+fn box_average(source: &RgbImage, col: usize, row: usize) -> Sample {
+    let (x0, x1) = span(col, source.width);
+    let (y0, y1) = span(row, source.height);
+    let mut sum = [0u32; 3];
+    for y in y0..y1 {
+        for x in x0..x1 {
+            let pixel = source.get(x, y);
+            sum[0] += pixel.r as u32;
+            sum[1] += pixel.g as u32;
+            sum[2] += pixel.b as u32;
+        }
+    }
+    let count = ((x1 - x0) * (y1 - y0)) as f64;
+    sum.map(|sum| sum as f64 / count)
+}
+
+/// Position of the center of cell `n` of 8 in source coordinates, as the
+/// lower source pixel and the weight of the next one.
+fn center(n: usize, size: usize) -> (usize, f32) {
+    let position = ((n as f32 + 0.5) * size as f32 / 8.0 - 0.5).clamp(0.0, (size - 1) as f32);
+    let lower = position as usize;
+    (lower, position - lower as f32)
+}
+
+fn bilinear(source: &RgbImage, col: usize, row: usize) -> Sample {
+    let (x0, fx) = center(col, source.width);
+    let (y0, fy) = center(row, source.height);
+    let x1 = (x0 + 1).min(source.width - 1);
+    let y1 = (y0 + 1).min(source.height - 1);
+    let lerp = |a: u8, b: u8, t: f32| a as f32 + (b as f32 - a as f32) * t;
+    let channel = |c: fn(Color) -> u8| {
+        let top = lerp(c(source.get(x0, y0)), c(source.get(x1, y0)), fx);
+        let bottom = lerp(c(source.get(x0, y1)), c(source.get(x1, y1)), fx);
+        (top + (bottom - top) * fy) as f64
+    };
+    [channel(|p| p.r), channel(|p| p.g), channel(|p| p.b)]
+}
+
+/// Decode the PNG, GIF or PPM file at `path`, recognized by its content.
+pub fn load(path: &Path) -> io::Result<Vec<Frame>> {
+    read(&fs::read(path)?)
+}
+
+/// Decode a PNG, GIF or PPM file, recognized by its first bytes.
+pub fn read(data: &[u8]) -> io::Result<Vec<Frame>> {
+    let still = |image| vec![Frame { image, delay_ms: None }];
+    match data {
+        [0x89, b'P', b'N', b'G', ..] => read_png(data).map(still),
+        [b'G', b'I', b'F', b'8', ..] => read_gif(data),
+        [b'P', b'3' | b'6', ..] => read_ppm(data).map(still),
+        _ => Err(invalid("Unknown image format")),
+    }
+}
+
+fn invalid<E>(error: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+/// Compose a color over black according to its alpha.
+fn over_black(r: u8, g: u8, b: u8, a: u8) -> Color {
+    let scale = |x: u8| ((x as u16 * a as u16 + 127) / 255) as u8;
+    Color { r: scale(r), g: scale(g), b: scale(b) }
+}
+
+/// Decode the first image of a PNG file.
+pub fn read_png(data: &[u8]) -> io::Result<RgbImage> {
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(invalid)?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).map_err(invalid)?;
+    if info.width == 0 || info.height == 0 {
+        return Err(invalid("Empty PNG image"));
+    }
+    let bytes = &buffer[..info.buffer_size()];
+    let pixels = match info.color_type {
+        png::ColorType::Rgb => bytes.chunks(3).map(|p| Color { r: p[0], g: p[1], b: p[2] }).collect(),
+        png::ColorType::Rgba => bytes.chunks(4).map(|p| over_black(p[0], p[1], p[2], p[3])).collect(),
+        png::ColorType::Grayscale => bytes.iter().map(|&l| Color { r: l, g: l, b: l }).collect(),
+        png::ColorType::GrayscaleAlpha => bytes.chunks(2).map(|p| over_black(p[0], p[0], p[0], p[1])).collect(),
+        png::ColorType::Indexed => return Err(invalid("Unexpanded indexed PNG")),
+    };
+    Ok(RgbImage::new(info.width as usize, info.height as usize, pixels))
+}
+
+/// Decode every frame of a GIF file, as displayed once composed on the
+/// logical screen.
+pub fn read_gif(data: &[u8]) -> io::Result<Vec<Frame>> {
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::RGBA);
+    let mut decoder = options.read_info(data).map_err(invalid)?;
+    let (width, height) = (decoder.width() as usize, decoder.height() as usize);
+    if width == 0 || height == 0 {
+        return Err(invalid("Empty GIF screen"));
+    }
+    // RGBA pixels of the logical screen
+    let mut screen = vec![0u8; width * height * 4];
+    let mut frames = Vec::new();
+    while let Some(frame) = decoder.read_next_frame().map_err(invalid)? {
+        let previous = screen.clone();
+        let (left, top) = (frame.left as usize, frame.top as usize);
+        // A frame without columns has no pixels, but chunks cannot be empty
+        let rows = frame.buffer.chunks(frame.width.max(1) as usize * 4);
+        for (y, line) in rows.enumerate().filter(|(y, _)| top + y < height) {
+            for (x, pixel) in line.chunks(4).enumerate().filter(|(x, _)| left + x < width) {
+                if pixel[3] != 0 {
+                    let at = ((top + y) * width + left + x) * 4;
+                    screen[at..at + 4].copy_from_slice(pixel);
+                }
+            }
+        }
+        let pixels = screen.chunks(4).map(|p| over_black(p[0], p[1], p[2], p[3])).collect();
+        frames.push(Frame {
+            image: RgbImage::new(width, height, pixels),
+            delay_ms: Some(frame.delay as u32 * 10).filter(|&delay| delay > 0),
+        });
+        match frame.dispose {
+            gif::DisposalMethod::Background => {
+                for y in top..(top + frame.height as usize).min(height) {
+                    let start = (y * width + left.min(width)) * 4;
+                    let end = (y * width + (left + frame.width as usize).min(width)) * 4;
+                    screen[start..end].fill(0);
+                }
+            }
+            gif::DisposalMethod::Previous => screen = previous,
+            _ => {}
+        }
+    }
+    if frames.is_empty() {
+        return Err(invalid("GIF without frames"));
+    }
+    Ok(frames)
+}
+
+/// Decode a binary (P6) or ASCII (P3) PPM file.
+pub fn read_ppm(data: &[u8]) -> io::Result<RgbImage> {
+    let binary = data.starts_with(b"P6");
+    // Header fields after the magic number, skipping comments
+    let mut pos = 2;
+    let mut field = || -> io::Result<usize> {
+        loop {
+            match data.get(pos) {
+                Some(b'#') => {
+                    while data.get(pos).is_some_and(|&b| b != b'\n') {
+                        pos += 1;
+                    }
+                }
+                Some(b) if b.is_ascii_whitespace() => pos += 1,
+                _ => break,
+            }
+        }
+        let start = pos;
+        while data.get(pos).is_some_and(u8::is_ascii_digit) {
+            pos += 1;
+        }
+        std::str::from_utf8(&data[start..pos]).unwrap().parse().map_err(|_| invalid("Invalid PPM header"))
+    };
+    let width = field()?;
+    let height = field()?;
+    let max = field()?;
+    if width == 0 || height == 0 {
+        return Err(invalid("Empty PPM image"));
+    }
+    let len = width.checked_mul(height).and_then(|n| n.checked_mul(3)).ok_or_else(|| invalid("PPM image too large"))?;
+    let samples = if binary {
+        if max == 0 || max > 0xffff {
+            return Err(invalid("Invalid PPM maximum value"));
+        }
+        // A single whitespace separates the header from the samples
+        pos += 1;
+        let size = if max > 0xff { 2 } else { 1 };
+        data.get(pos..)
+            .unwrap_or_default()
+            .chunks_exact(size)
+            .map(|s| s.iter().fold(0, |acc, &b| acc << 8 | b as usize))
+            .collect::<Vec<usize>>()
+    } else {
+        let mut samples = Vec::new();
+        for _ in 0..len {
+            samples.push(field()?);
+        }
+        samples
+    };
+    if samples.len() < len {
+        return Err(invalid("Truncated PPM file"));
+    }
+    let scale = |s: usize| (s.min(max) * 255 + max / 2).checked_div(max).unwrap_or(0) as u8;
+    let pixels = samples
+        .chunks(3)
+        .take(width * height)
+        .map(|s| Color { r: scale(s[0]), g: scale(s[1]), b: scale(s[2]) })
+        .collect();
+    Ok(RgbImage::new(width, height, pixels))
+}
@@ -0,0 +1,142 @@
+//! Dot correction of the DM163: every one of its 24 channels scales its
+//! output current by a 6-bit value latched into bank 0, independently from
+//! the 8-bit pixel data of bank 1.
+
+use crate::gamma::Channel;
+use crate::layout::PanelLayout;
+
+/// Largest dot-correction value, for the full output current.
+pub const MAX: u8 = 63;
+
+const fn limit(x: u8) -> u8 {
+    if x > MAX { MAX } else { x }
+}
+
+/// Dot-correction values of the 24 channels, as red, green and blue values
+/// for every column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DotCorrection(pub [[u8; 3]; 8]);
+
+impl Default for DotCorrection {
+    fn default() -> Self {
+        DotCorrection::FULL
+    }
+}
+
+impl DotCorrection {
+    /// Every channel at full current.
+    pub const FULL: DotCorrection = DotCorrection([[MAX; 3]; 8]);
+
+    /// Use the same value for a channel of every column. Values are limited
+    /// to `MAX`.
+    pub const fn uniform(red: u8, green: u8, blue: u8) -> Self {
+        DotCorrection([[limit(red), limit(green), limit(blue)]; 8])
+    }
+
+    /// Returns the value of a channel of a column.
+    pub fn get(&self, col: usize, channel: Channel) -> u8 {
+        self.0[col][channel as usize]
+    }
+
+    /// Set the value of a channel of a column, limited to `MAX`.
+    pub fn set(&mut self, col: usize, channel: Channel, value: u8) {
+        self.0[col][channel as usize] = value.min(MAX);
+    }
+
+    /// Scale every value by `brightness`/255, rounding to the nearest.
+    pub fn scaled(&self, brightness: u8) -> Self {
+        let scale = |x: u8| ((x as u16 * brightness as u16 + 127) / 255) as u8;
+        DotCorrection(self.0.map(|col| col.map(scale)))
+    }
+
+    /// Returns the values in the order they are shifted into the DM163 on a
+    /// panel wired as `layout`, the same as the pixels of a row: blue, green
+    /// then red of every column from the last column with
+    /// `PanelLayout::DEFAULT`.
+    pub fn shift_order(&self, layout: &PanelLayout) -> [u8; 24] {
+        layout.shift_order(&self.0)
+    }
+
+    /// Returns the 144 bits of bank 0 on a panel wired as `layout`: the
+    /// 6-bit values in shift order, most significant bit first, packed into
+    /// bytes.
+    pub fn to_bank0(&self, layout: &PanelLayout) -> [u8; BANK0_LEN] {
+        let mut bytes = [0; BANK0_LEN];
+        for (i, value) in self.shift_order(layout).into_iter().enumerate() {
+            for bit in 0..6 {
+                let n = 6 * i + bit;
+                bytes[n / 8] |= (value >> (5 - bit) & 1) << (7 - n % 8);
+            }
+        }
+        bytes
+    }
+}
+
+/// Number of bytes of the dot correction of the 24 channels.
+pub const BANK0_LEN: usize = 18;
+
+/// Number of bytes of an encoded `WhiteBalance`.
+pub const WHITE_BALANCE_LEN: usize = 4;
+
+/// Number of bytes of a `WhiteBalance` record in flash: the encoded
+/// calibration padded with erased bytes to a 64-bit double word, the unit
+/// of flash programming of the STM32L4.
+pub const WHITE_BALANCE_RECORD_LEN: usize = 8;
+
+/// Calibration of the LED colors, as the dot-correction value of every
+/// channel for a white of full brightness.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct WhiteBalance {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl Default for WhiteBalance {
+    fn default() -> Self {
+        WhiteBalance { red: MAX, green: MAX, blue: MAX }
+    }
+}
+
+impl WhiteBalance {
+    /// Create a calibration, if every value is at most `MAX`.
+    pub fn new(red: u8, green: u8, blue: u8) -> Option<Self> {
+        (red <= MAX && green <= MAX && blue <= MAX).then_some(WhiteBalance { red, green, blue })
+    }
+
+    /// Returns the dot correction of the calibration.
+    pub fn dot_correction(&self) -> DotCorrection {
+        DotCorrection::uniform(self.red, self.green, self.blue)
+    }
+
+    /// Encode the calibration for storage, as its red, green and blue
+    /// values followed by their XOR with 0xa5 as a check byte.
+    pub fn to_bytes(&self) -> [u8; WHITE_BALANCE_LEN] {
+        [self.red, self.green, self.blue, self.red ^ self.green ^ self.blue ^ 0xa5]
+    }
+
+    /// Decode a calibration encoded by `to_bytes()`. Erased or corrupted
+    /// storage gives `None`.
+    pub fn from_bytes(bytes: &[u8; WHITE_BALANCE_LEN]) -> Option<Self> {
+        let [red, green, blue, check] = *bytes;
+        if check != red ^ green ^ blue ^ 0xa5 {
+            return None;
+        }
+        WhiteBalance::new(red, green, blue)
+    }
+
+    /// Returns the record written to flash by the firmware to keep the
+    /// calibration across resets.
+    pub fn to_record(&self) -> [u8; WHITE_BALANCE_RECORD_LEN] {
+        let mut record = [0xff; WHITE_BALANCE_RECORD_LEN];
+        record[..WHITE_BALANCE_LEN].copy_from_slice(&self.to_bytes());
+        record
+    }
+
+    /// Decode a record written by `to_record()`. An erased page gives
+    /// `None`.
+    pub fn from_record(record: &[u8; WHITE_BALANCE_RECORD_LEN]) -> Option<Self> {
+        WhiteBalance::from_bytes(record[..WHITE_BALANCE_LEN].try_into().unwrap())
+    }
+}
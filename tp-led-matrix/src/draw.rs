@@ -0,0 +1,306 @@
+//! Drawing primitives on images.
+//!
+//! Coordinates are signed, `x` being the column and `y` the row, so that
+//! shapes may extend past the edges: pixels outside of the canvas are
+//! clipped.
+
+use core::fmt;
+
+use crate::{Color, Image};
+
+/// Largest number of spans `Canvas::flood_fill()` can keep to visit.
+pub const FILL_STACK_LEN: usize = 64;
+
+/// Region too complex for `Canvas::flood_fill()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FillError {
+    /// More than `FILL_STACK_LEN` spans were waiting to be visited, the
+    /// region is only partly filled.
+    StackOverflow,
+}
+
+impl fmt::Display for FillError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FillError::StackOverflow => write!(f, "more than {} spans left to fill", FILL_STACK_LEN),
+        }
+    }
+}
+
+/// Surface of pixels which can be drawn on.
+///
+/// Only the size and the bounds-checked access to pixels have to be
+/// implemented, every primitive is built on them.
+pub trait Canvas {
+    /// Number of columns.
+    fn width(&self) -> i32;
+
+    /// Number of rows.
+    fn height(&self) -> i32;
+
+    /// Returns the pixel at column `x` and row `y`, if it is on the canvas.
+    fn get_pixel(&self, x: i32, y: i32) -> Option<Color>;
+
+    /// Set the pixel at column `x` and row `y` if it is on the canvas.
+    /// Returns whether it was.
+    fn set_pixel(&mut self, x: i32, y: i32, color: Color) -> bool;
+
+    /// Returns whether a pixel is on the canvas.
+    fn contains(&self, x: i32, y: i32) -> bool {
+        (0..self.width()).contains(&x) && (0..self.height()).contains(&y)
+    }
+
+    /// Set every pixel to `color`.
+    fn fill(&mut self, color: Color) {
+        self.fill_rect(0, 0, self.width(), self.height(), color);
+    }
+
+    /// Draw a horizontal line on row `y`, from column `x0` to `x1` included.
+    fn hline(&mut self, x0: i32, x1: i32, y: i32, color: Color) {
+        if !(0..self.height()).contains(&y) {
+            return;
+        }
+        let (x0, x1) = (x0.min(x1).max(0), x0.max(x1).min(self.width() - 1));
+        for x in x0..=x1 {
+            self.set_pixel(x, y, color);
+        }
+    }
+
+    /// Draw a vertical line on column `x`, from row `y0` to `y1` included.
+    fn vline(&mut self, x: i32, y0: i32, y1: i32, color: Color) {
+        if !(0..self.width()).contains(&x) {
+            return;
+        }
+        let (y0, y1) = (y0.min(y1).max(0), y0.max(y1).min(self.height() - 1));
+        for y in y0..=y1 {
+            self.set_pixel(x, y, color);
+        }
+    }
+
+    /// Draw a line between two pixels included, with Bresenham's algorithm.
+    fn line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: Color) {
+        // Bresenham's algorithm puts the pixel of step `i` along the major
+        // axis at `(2 * i * minor + major) / (2 * major)` on the minor axis,
+        // so only the steps on the canvas are visited
+        let (dx, dy) = (i64::from(x1) - i64::from(x0), i64::from(y1) - i64::from(y0));
+        let x_major = dx.abs() >= dy.abs();
+        let (major, minor, start, size) = if x_major {
+            (dx, dy, x0, self.width())
+        } else {
+            (dy, dx, y0, self.height())
+        };
+        let (from, to) = (-i64::from(start), i64::from(size) - 1 - i64::from(start));
+        let (first, last) = if major < 0 { (-to, -from) } else { (from, to) };
+        let (major_len, minor_len) = (i128::from(major.abs()), i128::from(minor.abs()));
+        for i in first.max(0)..=last.min(major.abs()) {
+            let offset = match major_len {
+                0 => 0,
+                _ => ((2 * i128::from(i) * minor_len + major_len) / (2 * major_len)) as i64,
+            };
+            let along = (i64::from(start) + i * major.signum()) as i32;
+            let across = (i64::from(if x_major { y0 } else { x0 }) + offset * minor.signum()) as i32;
+            if x_major {
+                self.set_pixel(along, across, color);
+            } else {
+                self.set_pixel(across, along, color);
+            }
+        }
+    }
+
+    /// Draw the outline of a `width`×`height` rectangle whose top-left
+    /// pixel is at column `x` and row `y`.
+    fn rect(&mut self, x: i32, y: i32, width: i32, height: i32, color: Color) {
+        if width <= 0 || height <= 0 {
+            return;
+        }
+        let (right, bottom) = (x.saturating_add(width - 1), y.saturating_add(height - 1));
+        self.hline(x, right, y, color);
+        self.hline(x, right, bottom, color);
+        self.vline(x, y, bottom, color);
+        self.vline(right, y, bottom, color);
+    }
+
+    /// Fill a `width`×`height` rectangle whose top-left pixel is at column
+    /// `x` and row `y`.
+    fn fill_rect(&mut self, x: i32, y: i32, width: i32, height: i32, color: Color) {
+        if width <= 0 || height <= 0 {
+            return;
+        }
+        let (right, bottom) = (x.saturating_add(width - 1), y.saturating_add(height - 1));
+        for row in y.max(0)..=bottom.min(self.height() - 1) {
+            self.hline(x, right, row, color);
+        }
+    }
+
+    /// Draw the outline of a circle with the midpoint algorithm.
+    fn circle(&mut self, cx: i32, cy: i32, radius: i32, color: Color) {
+        let (width, height) = (self.width(), self.height());
+        for_circle_rows(cy, radius, height, |row, across, along| {
+            // A pixel at `across` columns from the center, and the pixels
+            // of the octants at `along` columns
+            if let Some(across) = across {
+                self.set_pixel(clip(cx, -across, width), row, color);
+                self.set_pixel(clip(cx, across, width), row, color);
+            }
+            if let Some((first, last)) = along {
+                self.hline(clip(cx, -last, width), clip(cx, -first, width), row, color);
+                self.hline(clip(cx, first, width), clip(cx, last, width), row, color);
+            }
+        });
+    }
+
+    /// Fill a circle, covering the same pixels as its outline and the ones
+    /// inside.
+    fn fill_circle(&mut self, cx: i32, cy: i32, radius: i32, color: Color) {
+        let (width, height) = (self.width(), self.height());
+        for_circle_rows(cy, radius, height, |row, across, along| {
+            let half = across.into_iter().chain(along.map(|(_, last)| last)).max().unwrap_or(0);
+            self.hline(clip(cx, -half, width), clip(cx, half, width), row, color);
+        });
+    }
+
+    /// Replace the color of the pixel at column `x` and row `y`, and of
+    /// every pixel of the same color connected to it horizontally or
+    /// vertically. Nothing is done if the pixel is outside of the canvas.
+    ///
+    /// The region is filled span by span, with at most `FILL_STACK_LEN`
+    /// spans left to visit: `FillError::StackOverflow` is returned if the
+    /// region needs more, after filling part of it.
+    fn flood_fill(&mut self, x: i32, y: i32, color: Color) -> Result<(), FillError> {
+        let target = match self.get_pixel(x, y) {
+            Some(target) if target != color => target,
+            _ => return Ok(()),
+        };
+        // Pixels of the spans left to fill
+        let mut stack = [(0, 0); FILL_STACK_LEN];
+        stack[0] = (x, y);
+        let mut len = 1;
+        while len > 0 {
+            len -= 1;
+            let (x, y) = stack[len];
+            if self.get_pixel(x, y) != Some(target) {
+                continue;
+            }
+            let (mut left, mut right) = (x, x);
+            while self.get_pixel(left - 1, y) == Some(target) {
+                left -= 1;
+            }
+            while self.get_pixel(right + 1, y) == Some(target) {
+                right += 1;
+            }
+            self.hline(left, right, y, color);
+            // One pixel of every span touching this one above and below
+            for row in [y - 1, y + 1] {
+                let mut in_span = false;
+                for col in left..=right {
+                    let matches = self.get_pixel(col, row) == Some(target);
+                    if matches && !in_span {
+                        if len == FILL_STACK_LEN {
+                            return Err(FillError::StackOverflow);
+                        }
+                        stack[len] = (col, row);
+                        len += 1;
+                    }
+                    in_span = matches;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Copy the pixels of `source` with its top-left pixel at column `x` and
+    /// row `y`, clipping the ones outside of this canvas.
+    fn blit<C: Canvas + ?Sized>(&mut self, source: &C, x: i32, y: i32) {
+        for row in 0..source.height() {
+            for col in 0..source.width() {
+                if let Some(color) = source.get_pixel(col, row) {
+                    self.set_pixel(x + col, y + row, color);
+                }
+            }
+        }
+    }
+}
+
+/// Largest value of `0..=max` for which `f` holds, `f` holding for the
+/// values up to it only.
+fn last_of(max: i64, f: impl Fn(i64) -> bool) -> Option<i64> {
+    if !f(0) {
+        return None;
+    }
+    let (mut low, mut high) = (0, max);
+    while low < high {
+        let mid = low + (high - low + 1) / 2;
+        if f(mid) {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    Some(low)
+}
+
+/// Column `cx + offset`, moved just outside of a canvas of `width` columns
+/// if it is outside of it.
+fn clip(cx: i32, offset: i64, width: i32) -> i32 {
+    (i64::from(cx) + offset).clamp(-1, i64::from(width)) as i32
+}
+
+/// Call `f` for every row of a canvas of `height` rows crossed by the
+/// outline of a circle drawn with the midpoint algorithm, with the column
+/// offsets from the center of its pixels on that row: a pixel on each side
+/// of the center, and a run of pixels on each side, as their offsets
+/// `(first, last)` on the right side.
+///
+/// The midpoint algorithm steps along the first octant, from the top of the
+/// circle, and picks at column offset `x` the largest row offset `y` with
+/// `y * (y - 1) + x * x < radius * radius`. The pixels of a row are found
+/// from this rule directly, so only the rows of the canvas are visited.
+fn for_circle_rows(cy: i32, radius: i32, height: i32, mut f: impl FnMut(i32, Option<i64>, Option<(i64, i64)>)) {
+    if radius < 0 {
+        return;
+    }
+    let radius = i64::from(radius);
+    let octant_y = |x: i64| {
+        let limit = i128::from(radius) * i128::from(radius) - i128::from(x) * i128::from(x);
+        last_of(radius, |y| i128::from(y) * i128::from(y - 1) < limit).unwrap_or(0)
+    };
+    // Last column offset of the octant, before the diagonal
+    let end = last_of(radius, |x| x <= octant_y(x)).unwrap_or(0);
+    for row in 0..height {
+        let y = (i64::from(row) - i64::from(cy)).abs();
+        if y > radius {
+            continue;
+        }
+        // Pixels of the octants mirrored on the diagonal, one per row
+        let across = (y <= end).then(|| octant_y(y));
+        // Pixels of the octants stepping along the rows
+        let along = last_of(end, |x| octant_y(x) >= y).map(|last| {
+            let first = last_of(end, |x| octant_y(x) > y).map_or(0, |x| x + 1);
+            (first, last)
+        });
+        f(row, across, along.filter(|(first, last)| first <= last));
+    }
+}
+
+impl<const W: usize, const H: usize> Canvas for Image<W, H> {
+    fn width(&self) -> i32 {
+        W as i32
+    }
+
+    fn height(&self) -> i32 {
+        H as i32
+    }
+
+    fn get_pixel(&self, x: i32, y: i32) -> Option<Color> {
+        self.contains(x, y).then(|| self[(y as usize, x as usize)])
+    }
+
+    fn set_pixel(&mut self, x: i32, y: i32, color: Color) -> bool {
+        let inside = self.contains(x, y);
+        if inside {
+            self[(y as usize, x as usize)] = color;
+        }
+        inside
+    }
+}
@@ -0,0 +1,308 @@
+//! Procedural animations rendered into images, for when nothing arrives on
+//! the serial port.
+//!
+//! Effects only use integer arithmetic and a seeded `Rng`, so that the same
+//! effect rendered at the same times gives the same frames on the target
+//! and on the host. Nothing is allocated.
+//!
+//! Times are in milliseconds. Effects with a state (`Fire`, `Twinkle` and
+//! `Life`) advance it by one step every period elapsed since the previous
+//! call, so they only run forward.
+
+use crate::draw::Canvas;
+use crate::image::BLACK;
+use crate::{Color, Image};
+
+/// Generator of animated images.
+pub trait Effect {
+    /// Draw the frame at time `t`, in milliseconds, over the whole of `out`.
+    fn render(&mut self, t: u32, out: &mut Image);
+}
+
+/// Xorshift pseudo-random number generator, reproducible from its seed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rng(u32);
+
+impl Rng {
+    /// Create a generator. A zero seed, which xorshift cannot use, is
+    /// replaced by another constant.
+    pub const fn new(seed: u32) -> Self {
+        Rng(if seed == 0 { 0x2545_f491 } else { seed })
+    }
+
+    /// Returns the next 32-bit value.
+    pub fn next_u32(&mut self) -> u32 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.0 = x;
+        x
+    }
+
+    /// Returns a value in [0, n), or 0 if `n` is 0.
+    pub fn below(&mut self, n: u32) -> u32 {
+        if n == 0 { 0 } else { ((self.next_u32() as u64 * n as u64) >> 32) as u32 }
+    }
+}
+
+/// Sine of `theta`, in 256ths of a turn, mapped to [1, 255] with a
+/// parabolic approximation.
+pub const fn sin8(theta: u8) -> u8 {
+    let x = (theta & 0x7f) as i32;
+    let y = x * (128 - x) / 32;
+    let y = if y > 127 { 127 } else { y };
+    (if theta < 128 { 128 + y } else { 128 - y }) as u8
+}
+
+/// Color of the rainbow wheel at `position`, from red through green and blue
+/// back to red.
+pub const fn wheel(position: u8) -> Color {
+    let p = position as u16 * 3;
+    match position {
+        0..=84 => Color { r: (255 - p) as u8, g: p as u8, b: 0 },
+        85..=169 => Color { r: 0, g: (510 - p) as u8, b: (p - 255) as u8 },
+        _ => Color { r: (p - 510) as u8, g: 0, b: (765 - p) as u8 },
+    }
+}
+
+/// Number of steps of `period` milliseconds between `*last` and `t`, at most
+/// `max`. `*last` moves forward by these steps. Times wrap around after
+/// `u32::MAX`, about 49 days.
+fn steps(last: &mut Option<u32>, t: u32, period: u32, max: u32) -> u32 {
+    let (start, period) = (*last.get_or_insert(t), period.max(1));
+    let n = t.wrapping_sub(start) / period;
+    *last = Some(start.wrapping_add(n * period));
+    n.min(max)
+}
+
+/// Interference of sine waves, colored with the rainbow wheel.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Plasma {
+    /// Milliseconds per 256th of a turn of the waves, at least 1.
+    pub speed: u32,
+}
+
+impl Default for Plasma {
+    fn default() -> Self {
+        Plasma { speed: 8 }
+    }
+}
+
+impl Effect for Plasma {
+    fn render(&mut self, t: u32, out: &mut Image) {
+        let phase = (t / self.speed.max(1)) as u8;
+        for row in 0..8u8 {
+            for col in 0..8u8 {
+                let sum = sin8(col.wrapping_mul(32).wrapping_add(phase)) as u32
+                    + sin8(row.wrapping_mul(24).wrapping_sub(phase.wrapping_mul(2))) as u32
+                    + sin8((col + row).wrapping_mul(16).wrapping_add(phase / 2)) as u32;
+                out[(row as usize, col as usize)] = wheel(((sum / 3) as u8).wrapping_add(phase / 4));
+            }
+        }
+    }
+}
+
+/// Diagonal bands of the rainbow wheel turning over time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rainbow {
+    /// Milliseconds per step of the wheel, at least 1.
+    pub speed: u32,
+    /// Steps of the wheel between neighbour columns or rows.
+    pub spread: u8,
+}
+
+impl Default for Rainbow {
+    fn default() -> Self {
+        Rainbow { speed: 10, spread: 16 }
+    }
+}
+
+impl Effect for Rainbow {
+    fn render(&mut self, t: u32, out: &mut Image) {
+        let phase = (t / self.speed.max(1)) as u8;
+        for row in 0..8 {
+            for col in 0..8 {
+                let offset = self.spread.wrapping_mul((row + col) as u8);
+                out[(row, col)] = wheel(phase.wrapping_add(offset));
+            }
+        }
+    }
+}
+
+/// Color of fire with the given heat, from black through red and yellow to
+/// white.
+pub const fn heat_color(heat: u8) -> Color {
+    match heat {
+        0..=84 => Color { r: heat * 3, g: 0, b: 0 },
+        85..=169 => Color { r: 255, g: (heat - 85) * 3, b: 0 },
+        _ => Color { r: 255, g: 255, b: (heat - 170) * 3 },
+    }
+}
+
+/// Flames rising from the bottom row.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fire {
+    heat: [[u8; 8]; 8],
+    rng: Rng,
+    last: Option<u32>,
+    /// Milliseconds between steps.
+    pub period: u32,
+    /// Largest heat lost by every cell at every step.
+    pub cooling: u8,
+    /// Chance in 256 of a spark in every column of the bottom row at every
+    /// step.
+    pub sparking: u8,
+}
+
+impl Fire {
+    /// Create a cold fire whose sparks are drawn from `seed`.
+    pub const fn new(seed: u32) -> Self {
+        Fire { heat: [[0; 8]; 8], rng: Rng::new(seed), last: None, period: 30, cooling: 80, sparking: 96 }
+    }
+
+    fn step(&mut self) {
+        for cell in self.heat.iter_mut().flatten() {
+            *cell = cell.saturating_sub(self.rng.below(self.cooling as u32 + 1) as u8);
+        }
+        // Heat drifts up from the two cells below, the bottom row drifting
+        // into the one above it
+        for row in 0..7 {
+            for col in 0..8 {
+                let below = self.heat[row + 1][col] as u32;
+                let further = self.heat[(row + 2).min(7)][col] as u32;
+                self.heat[row][col] = ((below + 2 * further) / 3) as u8;
+            }
+        }
+        for col in 0..8 {
+            if self.rng.below(256) < self.sparking as u32 {
+                self.heat[7][col] = self.heat[7][col].saturating_add(160 + self.rng.below(96) as u8);
+            }
+        }
+    }
+}
+
+impl Effect for Fire {
+    fn render(&mut self, t: u32, out: &mut Image) {
+        for _ in 0..steps(&mut self.last, t, self.period, 16) {
+            self.step();
+        }
+        for row in 0..8 {
+            for col in 0..8 {
+                out[(row, col)] = heat_color(self.heat[row][col]);
+            }
+        }
+    }
+}
+
+/// Pixels lighting up at random in colors of the rainbow wheel, then fading
+/// out.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Twinkle {
+    image: Image,
+    rng: Rng,
+    last: Option<u32>,
+    /// Milliseconds between steps.
+    pub period: u32,
+    /// Brightness kept by every pixel at every step, in 256ths.
+    pub fade: u8,
+    /// Chance in 256 of a pixel lighting up at every step.
+    pub density: u8,
+}
+
+impl Twinkle {
+    /// Create a dark sky whose stars are drawn from `seed`.
+    pub fn new(seed: u32) -> Self {
+        Twinkle { image: Image::default(), rng: Rng::new(seed), last: None, period: 50, fade: 224, density: 160 }
+    }
+
+    fn step(&mut self) {
+        self.image = self.image.scale8(self.fade);
+        if self.rng.below(256) < self.density as u32 {
+            let (x, y) = (self.rng.below(8) as i32, self.rng.below(8) as i32);
+            let color = wheel(self.rng.below(256) as u8);
+            self.image.set_pixel(x, y, color);
+        }
+    }
+}
+
+impl Effect for Twinkle {
+    fn render(&mut self, t: u32, out: &mut Image) {
+        for _ in 0..steps(&mut self.last, t, self.period, 16) {
+            self.step();
+        }
+        *out = self.image;
+    }
+}
+
+/// Conway's Game of Life on a torus: the left and right edges touch, and so
+/// do the top and bottom ones. Cells are the bits of a `u64`, bit `8*row+col`
+/// being set for a live cell.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Life {
+    cells: u64,
+    rng: Rng,
+    last: Option<u32>,
+    /// Milliseconds between generations.
+    pub period: u32,
+    /// Color of live cells, dead ones being black.
+    pub color: Color,
+}
+
+impl Life {
+    /// Create a game starting from `cells`. Empty or frozen games restart
+    /// from random cells drawn from `seed`.
+    pub const fn new(cells: u64, seed: u32, color: Color) -> Self {
+        Life { cells, rng: Rng::new(seed), last: None, period: 250, color }
+    }
+
+    /// Create a game starting from random cells drawn from `seed`.
+    pub fn random(seed: u32, color: Color) -> Self {
+        let mut life = Life::new(0, seed, color);
+        life.reseed();
+        life
+    }
+
+    /// Returns the live cells.
+    pub fn cells(&self) -> u64 {
+        self.cells
+    }
+
+    fn reseed(&mut self) {
+        self.cells = (self.rng.next_u32() as u64) << 32 | self.rng.next_u32() as u64;
+    }
+
+    /// Returns the cells of the next generation.
+    pub fn next_generation(cells: u64) -> u64 {
+        let alive = |row: usize, col: usize| (cells >> (8 * (row % 8) + col % 8) & 1) as u32;
+        let mut next = 0;
+        for row in 0..8 {
+            for col in 0..8 {
+                let neighbours: u32 = [(7, 7), (7, 0), (7, 1), (0, 7), (0, 1), (1, 7), (1, 0), (1, 1)]
+                    .iter()
+                    .map(|&(dr, dc)| alive(row + dr, col + dc))
+                    .sum();
+                if neighbours == 3 || neighbours == 2 && alive(row, col) == 1 {
+                    next |= 1 << (8 * row + col);
+                }
+            }
+        }
+        next
+    }
+}
+
+impl Effect for Life {
+    fn render(&mut self, t: u32, out: &mut Image) {
+        for _ in 0..steps(&mut self.last, t, self.period, 16) {
+            let next = Life::next_generation(self.cells);
+            if next == 0 || next == self.cells {
+                self.reseed();
+            } else {
+                self.cells = next;
+            }
+        }
+        for n in 0..64 {
+            out[(n / 8, n % 8)] = if self.cells >> n & 1 == 1 { self.color } else { BLACK };
+        }
+    }
+}
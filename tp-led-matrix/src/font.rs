@@ -0,0 +1,223 @@
+//! Built-in 5×7 bitmap font and scrolling text.
+//!
+//! Every glyph is 5 columns wide and 7 rows high, and is followed by an
+//! empty column. Glyphs are drawn from the top row, the 8th row of the
+//! matrix staying in the background color.
+
+use crate::draw::Canvas;
+use crate::{Color, Image};
+
+/// Width of a glyph, in pixels.
+pub const GLYPH_WIDTH: i32 = 5;
+
+/// Height of a glyph, in pixels.
+pub const GLYPH_HEIGHT: i32 = 7;
+
+/// Horizontal distance between two glyphs: their width and an empty column.
+pub const ADVANCE: i32 = GLYPH_WIDTH + 1;
+
+/// Glyphs of the printable ASCII characters from ' ' to '~', as columns from
+/// left to right whose least significant bit is the top row.
+const ASCII: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x00, 0x00, 0x5F, 0x00, 0x00], // '!'
+    [0x00, 0x07, 0x00, 0x07, 0x00], // '"'
+    [0x14, 0x7F, 0x14, 0x7F, 0x14], // '#'
+    [0x24, 0x2A, 0x7F, 0x2A, 0x12], // '$'
+    [0x23, 0x13, 0x08, 0x64, 0x62], // '%'
+    [0x36, 0x49, 0x55, 0x22, 0x50], // '&'
+    [0x00, 0x05, 0x03, 0x00, 0x00], // '\''
+    [0x00, 0x1C, 0x22, 0x41, 0x00], // '('
+    [0x00, 0x41, 0x22, 0x1C, 0x00], // ')'
+    [0x08, 0x2A, 0x1C, 0x2A, 0x08], // '*'
+    [0x08, 0x08, 0x3E, 0x08, 0x08], // '+'
+    [0x00, 0x50, 0x30, 0x00, 0x00], // ','
+    [0x08, 0x08, 0x08, 0x08, 0x08], // '-'
+    [0x00, 0x60, 0x60, 0x00, 0x00], // '.'
+    [0x20, 0x10, 0x08, 0x04, 0x02], // '/'
+    [0x3E, 0x51, 0x49, 0x45, 0x3E], // '0'
+    [0x00, 0x42, 0x7F, 0x40, 0x00], // '1'
+    [0x42, 0x61, 0x51, 0x49, 0x46], // '2'
+    [0x21, 0x41, 0x45, 0x4B, 0x31], // '3'
+    [0x18, 0x14, 0x12, 0x7F, 0x10], // '4'
+    [0x27, 0x45, 0x45, 0x45, 0x39], // '5'
+    [0x3C, 0x4A, 0x49, 0x49, 0x30], // '6'
+    [0x01, 0x71, 0x09, 0x05, 0x03], // '7'
+    [0x36, 0x49, 0x49, 0x49, 0x36], // '8'
+    [0x06, 0x49, 0x49, 0x29, 0x1E], // '9'
+    [0x00, 0x36, 0x36, 0x00, 0x00], // ':'
+    [0x00, 0x56, 0x36, 0x00, 0x00], // ';'
+    [0x08, 0x14, 0x22, 0x41, 0x00], // '<'
+    [0x14, 0x14, 0x14, 0x14, 0x14], // '='
+    [0x00, 0x41, 0x22, 0x14, 0x08], // '>'
+    [0x02, 0x01, 0x51, 0x09, 0x06], // '?'
+    [0x32, 0x49, 0x79, 0x41, 0x3E], // '@'
+    [0x7E, 0x11, 0x11, 0x11, 0x7E], // 'A'
+    [0x7F, 0x49, 0x49, 0x49, 0x36], // 'B'
+    [0x3E, 0x41, 0x41, 0x41, 0x22], // 'C'
+    [0x7F, 0x41, 0x41, 0x22, 0x1C], // 'D'
+    [0x7F, 0x49, 0x49, 0x49, 0x41], // 'E'
+    [0x7F, 0x09, 0x09, 0x01, 0x01], // 'F'
+    [0x3E, 0x41, 0x41, 0x51, 0x32], // 'G'
+    [0x7F, 0x08, 0x08, 0x08, 0x7F], // 'H'
+    [0x00, 0x41, 0x7F, 0x41, 0x00], // 'I'
+    [0x20, 0x40, 0x41, 0x3F, 0x01], // 'J'
+    [0x7F, 0x08, 0x14, 0x22, 0x41], // 'K'
+    [0x7F, 0x40, 0x40, 0x40, 0x40], // 'L'
+    [0x7F, 0x02, 0x04, 0x02, 0x7F], // 'M'
+    [0x7F, 0x04, 0x08, 0x10, 0x7F], // 'N'
+    [0x3E, 0x41, 0x41, 0x41, 0x3E], // 'O'
+    [0x7F, 0x09, 0x09, 0x09, 0x06], // 'P'
+    [0x3E, 0x41, 0x51, 0x21, 0x5E], // 'Q'
+    [0x7F, 0x09, 0x19, 0x29, 0x46], // 'R'
+    [0x46, 0x49, 0x49, 0x49, 0x31], // 'S'
+    [0x01, 0x01, 0x7F, 0x01, 0x01], // 'T'
+    [0x3F, 0x40, 0x40, 0x40, 0x3F], // 'U'
+    [0x1F, 0x20, 0x40, 0x20, 0x1F], // 'V'
+    [0x7F, 0x20, 0x18, 0x20, 0x7F], // 'W'
+    [0x63, 0x14, 0x08, 0x14, 0x63], // 'X'
+    [0x03, 0x04, 0x78, 0x04, 0x03], // 'Y'
+    [0x61, 0x51, 0x49, 0x45, 0x43], // 'Z'
+    [0x00, 0x7F, 0x41, 0x41, 0x00], // '['
+    [0x02, 0x04, 0x08, 0x10, 0x20], // '\\'
+    [0x00, 0x41, 0x41, 0x7F, 0x00], // ']'
+    [0x04, 0x02, 0x01, 0x02, 0x04], // '^'
+    [0x40, 0x40, 0x40, 0x40, 0x40], // '_'
+    [0x00, 0x01, 0x02, 0x04, 0x00], // '`'
+    [0x20, 0x54, 0x54, 0x54, 0x78], // 'a'
+    [0x7F, 0x48, 0x44, 0x44, 0x38], // 'b'
+    [0x38, 0x44, 0x44, 0x44, 0x20], // 'c'
+    [0x38, 0x44, 0x44, 0x48, 0x7F], // 'd'
+    [0x38, 0x54, 0x54, 0x54, 0x18], // 'e'
+    [0x08, 0x7E, 0x09, 0x01, 0x02], // 'f'
+    [0x08, 0x14, 0x54, 0x54, 0x3C], // 'g'
+    [0x7F, 0x08, 0x04, 0x04, 0x78], // 'h'
+    [0x00, 0x44, 0x7D, 0x40, 0x00], // 'i'
+    [0x20, 0x40, 0x44, 0x3D, 0x00], // 'j'
+    [0x00, 0x7F, 0x10, 0x28, 0x44], // 'k'
+    [0x00, 0x41, 0x7F, 0x40, 0x00], // 'l'
+    [0x7C, 0x04, 0x18, 0x04, 0x78], // 'm'
+    [0x7C, 0x08, 0x04, 0x04, 0x78], // 'n'
+    [0x38, 0x44, 0x44, 0x44, 0x38], // 'o'
+    [0x7C, 0x14, 0x14, 0x14, 0x08], // 'p'
+    [0x08, 0x14, 0x14, 0x18, 0x7C], // 'q'
+    [0x7C, 0x08, 0x04, 0x04, 0x08], // 'r'
+    [0x48, 0x54, 0x54, 0x54, 0x20], // 's'
+    [0x04, 0x3F, 0x44, 0x40, 0x20], // 't'
+    [0x3C, 0x40, 0x40, 0x20, 0x7C], // 'u'
+    [0x1C, 0x20, 0x40, 0x20, 0x1C], // 'v'
+    [0x3C, 0x40, 0x30, 0x40, 0x3C], // 'w'
+    [0x44, 0x28, 0x10, 0x28, 0x44], // 'x'
+    [0x0C, 0x50, 0x50, 0x50, 0x3C], // 'y'
+    [0x44, 0x64, 0x54, 0x4C, 0x44], // 'z'
+    [0x00, 0x08, 0x36, 0x41, 0x00], // '{'
+    [0x00, 0x00, 0x7F, 0x00, 0x00], // '|'
+    [0x00, 0x41, 0x36, 0x08, 0x00], // '}'
+    [0x08, 0x04, 0x08, 0x10, 0x08], // '~'
+];
+
+/// Glyphs of the symbols outside of ASCII.
+const SYMBOLS: [(char, [u8; 5]); 8] = [
+    ('°', [0x00, 0x06, 0x09, 0x09, 0x06]),
+    ('♥', [0x0C, 0x1E, 0x3C, 0x1E, 0x0C]),
+    ('←', [0x08, 0x1C, 0x2A, 0x08, 0x08]),
+    ('→', [0x08, 0x08, 0x2A, 0x1C, 0x08]),
+    ('↑', [0x04, 0x02, 0x7F, 0x02, 0x04]),
+    ('↓', [0x10, 0x20, 0x7F, 0x20, 0x10]),
+    ('█', [0x7F, 0x7F, 0x7F, 0x7F, 0x7F]),
+    ('·', [0x00, 0x00, 0x08, 0x00, 0x00]),
+];
+
+/// Glyph of the characters without one.
+const UNKNOWN: [u8; 5] = [0x7F, 0x41, 0x41, 0x41, 0x7F];
+
+/// Returns the columns of the glyph of `c`, from left to right, the least
+/// significant bit being the top row. Characters without a glyph are drawn
+/// as a hollow box.
+pub fn glyph(c: char) -> [u8; 5] {
+    match c {
+        ' '..='~' => ASCII[c as usize - ' ' as usize],
+        _ => SYMBOLS.iter().find(|(s, _)| *s == c).map_or(UNKNOWN, |&(_, glyph)| glyph),
+    }
+}
+
+/// Returns the width of `text` drawn by `draw_text()`, including the empty
+/// column after the last glyph.
+pub fn text_width(text: &str) -> i32 {
+    text.chars().count() as i32 * ADVANCE
+}
+
+/// Draw `c` with its left column at `x`, the pixels of the glyph in `fg` and
+/// the rest of its `ADVANCE` columns in `bg`. Returns the position of the
+/// next glyph.
+pub fn draw_char<C: Canvas + ?Sized>(canvas: &mut C, c: char, x: i32, fg: Color, bg: Color) -> i32 {
+    let columns = glyph(c);
+    for col in 0..ADVANCE {
+        let bits = columns.get(col as usize).copied().unwrap_or(0);
+        for row in 0..canvas.height() {
+            let lit = row < GLYPH_HEIGHT && bits >> row & 1 != 0;
+            canvas.set_pixel(x + col, row, if lit { fg } else { bg });
+        }
+    }
+    x + ADVANCE
+}
+
+/// Draw `text` from the column `x`, as `draw_char()` does, skipping the
+/// glyphs outside of the canvas. Returns the position after the text.
+pub fn draw_text<C: Canvas + ?Sized>(canvas: &mut C, text: &str, x: i32, fg: Color, bg: Color) -> i32 {
+    let end = x + text_width(text);
+    let mut x = x;
+    for c in text.chars() {
+        if x >= canvas.width() {
+            break;
+        }
+        if x + ADVANCE > 0 {
+            draw_char(canvas, c, x, fg, bg);
+        }
+        x += ADVANCE;
+    }
+    end
+}
+
+/// Iterator over the frames of `text` scrolling from right to left, one
+/// column per frame. The first frame is empty and the last one shows the
+/// last column of the text on the left edge, so that the frames can be
+/// repeated with `cycle()`.
+#[derive(Clone, Debug)]
+pub struct Marquee<'a> {
+    text: &'a str,
+    fg: Color,
+    bg: Color,
+    /// Index of the next frame
+    frame: i32,
+    frames: i32,
+}
+
+impl<'a> Marquee<'a> {
+    /// Scroll `text` in `fg` over `bg`.
+    pub fn new(text: &'a str, fg: Color, bg: Color) -> Self {
+        let frames = if text.is_empty() { 0 } else { 8 + text_width(text) - 1 };
+        Marquee { text, fg, bg, frame: 0, frames }
+    }
+}
+
+impl Iterator for Marquee<'_> {
+    type Item = Image;
+
+    fn next(&mut self) -> Option<Image> {
+        if self.frame >= self.frames {
+            return None;
+        }
+        let mut image = Image::new_solid(self.bg);
+        draw_text(&mut image, self.text, 8 - self.frame, self.fg, self.bg);
+        self.frame += 1;
+        Some(image)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.frames - self.frame) as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for Marquee<'_> {}
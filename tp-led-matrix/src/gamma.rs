@@ -1,3 +1,7 @@
+use core::f64::consts::LN_2;
+
+use crate::Color;
+
 const GAMMA_TAB: [u8; 256] = [
 0x00, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x02, 0x02, 0x02, 0x02, 0x02, 0x03,
 0x03, 0x03, 0x03, 0x04, 0x04, 0x04, 0x04, 0x05, 0x05, 0x05, 0x06, 0x06, 0x06, 0x07, 0x07, 0x08,
@@ -22,3 +26,219 @@ pub fn gamma_correct(x: u8) -> u8 {
     return GAMMA_TAB[x as usize];
 }
 
+/// Inverse of `gamma_correct()`: returns the smallest value which is
+/// corrected to at least `y`.
+pub fn gamma_uncorrect(y: u8) -> u8 {
+    // GAMMA_TAB[255] is 0xff, so some value is always found
+    GAMMA_TAB.iter().position(|&x| x >= y).unwrap() as u8
+}
+
+/// Primary color of a pixel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Channel {
+    Red,
+    Green,
+    Blue,
+}
+
+/// Correction applied to every primary color before it is sent to the
+/// DM163, as one 256-entry table per channel.
+///
+/// Curves can be built in `const` items, so that their tables are computed
+/// at compile time and stored in flash, or at runtime.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GammaCurve {
+    tables: [[u8; 256]; 3],
+}
+
+impl GammaCurve {
+    /// No correction.
+    pub const IDENTITY: GammaCurve = GammaCurve::identity();
+
+    /// The correction of `gamma_correct()`.
+    pub const STANDARD: GammaCurve = GammaCurve::from_table(GAMMA_TAB);
+
+    /// Decoding of sRGB values to linear light.
+    pub const SRGB: GammaCurve = GammaCurve::srgb();
+
+    /// Create a curve applying no correction.
+    pub const fn identity() -> Self {
+        let mut table = [0; 256];
+        let mut i = 0;
+        while i < 256 {
+            table[i] = i as u8;
+            i += 1;
+        }
+        GammaCurve::from_table(table)
+    }
+
+    /// Create a curve applying the same table to every channel.
+    pub const fn from_table(table: [u8; 256]) -> Self {
+        GammaCurve { tables: [table; 3] }
+    }
+
+    /// Create a curve with one table per channel.
+    pub const fn from_tables(red: [u8; 256], green: [u8; 256], blue: [u8; 256]) -> Self {
+        GammaCurve { tables: [red, green, blue] }
+    }
+
+    /// Create a curve raising every normalized value to the power `gamma`.
+    pub const fn power(gamma: f32) -> Self {
+        GammaCurve::from_table(power_table(gamma as f64))
+    }
+
+    /// Create a curve with one exponent per channel, for example to
+    /// compensate for LEDs of unequal efficiency.
+    pub const fn power_rgb(red: f32, green: f32, blue: f32) -> Self {
+        GammaCurve::from_tables(power_table(red as f64), power_table(green as f64), power_table(blue as f64))
+    }
+
+    /// Create the sRGB decoding curve: linear near black, then a power of 2.4.
+    pub const fn srgb() -> Self {
+        let mut table = [0; 256];
+        let mut i = 0;
+        while i < 256 {
+            let c = i as f64 / 255.0;
+            let linear = if c <= 0.04045 { c / 12.92 } else { pow((c + 0.055) / 1.055, 2.4) };
+            table[i] = to_byte(linear);
+            i += 1;
+        }
+        GammaCurve::from_table(table)
+    }
+
+    /// Returns the table of a channel.
+    pub fn table(&self, channel: Channel) -> &[u8; 256] {
+        &self.tables[channel as usize]
+    }
+
+    /// Applies the correction to every primary color of a pixel.
+    pub fn correct(&self, color: Color) -> Color {
+        Color {
+            r: self.tables[Channel::Red as usize][color.r as usize],
+            g: self.tables[Channel::Green as usize][color.g as usize],
+            b: self.tables[Channel::Blue as usize][color.b as usize],
+        }
+    }
+}
+
+impl Default for GammaCurve {
+    fn default() -> Self {
+        GammaCurve::STANDARD
+    }
+}
+
+/// Scale a value between 0 and 1 to a rounded byte.
+const fn to_byte(x: f64) -> u8 {
+    (x * 255.0 + 0.5) as u8
+}
+
+const fn power_table(gamma: f64) -> [u8; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        table[i] = to_byte(pow(i as f64 / 255.0, gamma));
+        i += 1;
+    }
+    table
+}
+
+/// `x` to the power `y`, for `x` between 0 and 1 and `y` positive. `f64::powf`
+/// cannot be used in `const fn`, nor without `std`.
+pub(crate) const fn pow(x: f64, y: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    exp(y * ln(x))
+}
+
+/// Natural logarithm of a positive number.
+const fn ln(x: f64) -> f64 {
+    // x = m × 2^e with m in [1, 2), and ln(m) = 2 atanh((m - 1) / (m + 1))
+    let mut m = x;
+    let mut e = 0;
+    while m >= 2.0 {
+        m /= 2.0;
+        e += 1;
+    }
+    while m < 1.0 {
+        m *= 2.0;
+        e -= 1;
+    }
+    let t = (m - 1.0) / (m + 1.0);
+    let mut power = t;
+    let mut sum = 0.0;
+    let mut k = 1;
+    while k < 40 {
+        sum += power / k as f64;
+        power *= t * t;
+        k += 2;
+    }
+    2.0 * sum + e as f64 * LN_2
+}
+
+/// Exponential of a number.
+const fn exp(x: f64) -> f64 {
+    // x = k ln(2) + r with |r| <= ln(2) / 2
+    let k = if x >= 0.0 { (x / LN_2 + 0.5) as i32 } else { (x / LN_2 - 0.5) as i32 };
+    let r = x - k as f64 * LN_2;
+    let mut term = 1.0;
+    let mut sum = 1.0;
+    let mut n = 1;
+    while n < 20 {
+        term *= r / n as f64;
+        sum += term;
+        n += 1;
+    }
+    let mut k = k;
+    while k > 0 {
+        sum *= 2.0;
+        k -= 1;
+    }
+    while k < 0 {
+        sum /= 2.0;
+        k += 1;
+    }
+    sum
+}
+
+/// Gamma curves which can be selected at runtime.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Preset {
+    /// No correction.
+    Linear,
+    /// The correction of `gamma_correct()`.
+    Standard,
+    /// Decoding of sRGB values.
+    Srgb,
+}
+
+impl Preset {
+    /// Returns the curve of the preset.
+    pub fn curve(&self) -> GammaCurve {
+        match self {
+            Preset::Linear => GammaCurve::IDENTITY,
+            Preset::Standard => GammaCurve::STANDARD,
+            Preset::Srgb => GammaCurve::SRGB,
+        }
+    }
+
+    /// Returns the identifier of the preset in commands.
+    pub fn id(&self) -> u8 {
+        match self {
+            Preset::Linear => 0,
+            Preset::Standard => 1,
+            Preset::Srgb => 2,
+        }
+    }
+
+    /// Returns the preset with the given identifier, if any.
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Preset::Linear),
+            1 => Some(Preset::Standard),
+            2 => Some(Preset::Srgb),
+            _ => None,
+        }
+    }
+}
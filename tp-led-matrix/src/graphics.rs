@@ -0,0 +1,52 @@
+//! Rendering into images with embedded-graphics: `Image` is a `DrawTarget`
+//! of `Rgb888` pixels, so that its shapes, fonts and image decoders draw
+//! frames ready for `Matrix::display_image()`.
+
+use core::convert::Infallible;
+
+use embedded_graphics_core::draw_target::DrawTarget;
+use embedded_graphics_core::geometry::{OriginDimensions, Size};
+use embedded_graphics_core::pixelcolor::{Rgb888, RgbColor};
+use embedded_graphics_core::Pixel;
+
+use crate::draw::Canvas;
+use crate::{Color, Image};
+
+impl From<Rgb888> for Color {
+    fn from(color: Rgb888) -> Self {
+        Color { r: color.r(), g: color.g(), b: color.b() }
+    }
+}
+
+impl From<Color> for Rgb888 {
+    fn from(color: Color) -> Self {
+        Rgb888::new(color.r, color.g, color.b)
+    }
+}
+
+impl<const W: usize, const H: usize> OriginDimensions for Image<W, H> {
+    fn size(&self) -> Size {
+        Size::new(W as u32, H as u32)
+    }
+}
+
+impl<const W: usize, const H: usize> DrawTarget for Image<W, H> {
+    type Color = Rgb888;
+    type Error = Infallible;
+
+    /// Set the pixels on the image, ignoring the ones outside of it.
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            self.set_pixel(point.x, point.y, color.into());
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        *self = Image::new_solid(color.into());
+        Ok(())
+    }
+}
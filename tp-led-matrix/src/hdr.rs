@@ -0,0 +1,267 @@
+//! Colors with 12 bits per channel, rendered by temporal dithering.
+//!
+//! The DM163 only takes 8-bit pixel data, and the gamma correction maps the
+//! dimmest colors to the same few output values. An `HdrImage` is instead
+//! corrected with 12 bits of output precision by an `HdrGammaCurve`, then
+//! every output value is split into its 8 most significant bits and a 4-bit
+//! fraction: over `SUBFRAMES` consecutive frames, the 8-bit value is rounded
+//! up in as many frames as the fraction, so that the average output keeps
+//! the 12 bits.
+//!
+//! Dithered frames are refreshed at `DITHERED_FPS`, so that the whole
+//! sequence repeats at `DITHER_CYCLE_HZ` without visible flicker.
+//!
+//! 12-bit images are sent on the serial link packed by `HdrImage::packed()`
+//! (see `protocol::encode_hdr()`).
+
+use crate::gamma::{self, Preset};
+use crate::{Color, Image};
+
+/// Largest value of a channel.
+pub const HDR_MAX: u16 = 4095;
+
+/// Number of frames over which the output values are dithered.
+pub const SUBFRAMES: usize = 16;
+
+/// Refresh rate of the dithered mode, in frames per second. A row is then
+/// sent every 130µs.
+pub const DITHERED_FPS: u32 = 960;
+
+/// Rate at which the sequence of `SUBFRAMES` frames repeats.
+pub const DITHER_CYCLE_HZ: u32 = DITHERED_FPS / SUBFRAMES as u32;
+
+/// Number of bytes of a packed 8×8 `HdrImage`: two 12-bit channels every
+/// three bytes.
+pub const HDR_FRAME_LEN: usize = HdrImage::<8, 8>::BYTES;
+
+/// Pixel with 12 bits per channel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HdrColor {
+    pub r: u16,
+    pub g: u16,
+    pub b: u16,
+}
+
+impl From<Color> for HdrColor {
+    /// Extend 8-bit channels to 12 bits, mapping 0xff to `HDR_MAX`.
+    fn from(color: Color) -> Self {
+        let extend = |x: u8| (x as u16) << 4 | (x as u16) >> 4;
+        HdrColor { r: extend(color.r), g: extend(color.g), b: extend(color.b) }
+    }
+}
+
+impl HdrColor {
+    /// Keep the 8 most significant bits of every channel.
+    pub fn to_color(&self) -> Color {
+        let reduce = |x: u16| (x.min(HDR_MAX) >> 4) as u8;
+        Color { r: reduce(self.r), g: reduce(self.g), b: reduce(self.b) }
+    }
+}
+
+/// W×H image with 12 bits per channel, 8×8 by default like `Image`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HdrImage<const W: usize = 8, const H: usize = 8>(pub [[HdrColor; W]; H]);
+
+impl<const W: usize, const H: usize> Default for HdrImage<W, H> {
+    fn default() -> Self {
+        HdrImage([[HdrColor::default(); W]; H])
+    }
+}
+
+impl<const W: usize, const H: usize> From<&Image<W, H>> for HdrImage<W, H> {
+    fn from(image: &Image<W, H>) -> Self {
+        HdrImage(image.0.map(|row| row.map(HdrColor::from)))
+    }
+}
+
+impl<const W: usize, const H: usize> core::ops::Index<(usize, usize)> for HdrImage<W, H> {
+    type Output = HdrColor;
+    fn index(&self, index: (usize, usize)) -> &Self::Output {
+        &self.0[index.0][index.1]
+    }
+}
+
+impl<const W: usize, const H: usize> core::ops::IndexMut<(usize, usize)> for HdrImage<W, H> {
+    fn index_mut(&mut self, index: (usize, usize)) -> &mut Self::Output {
+        &mut self.0[index.0][index.1]
+    }
+}
+
+impl<const W: usize, const H: usize> HdrImage<W, H> {
+    /// Number of bytes of the packed image: two 12-bit channels every three
+    /// bytes, the last channel being paired with 0 if their number is odd.
+    pub const BYTES: usize = (W * H * 3).div_ceil(2) * 3;
+
+    /// Returns a row of the image.
+    pub fn row(&self, row: usize) -> &[HdrColor] {
+        &self.0[row]
+    }
+
+    /// Keep the 8 most significant bits of every channel.
+    pub fn to_image(&self) -> Image<W, H> {
+        Image(self.0.map(|row| row.map(|pixel| pixel.to_color())))
+    }
+
+    /// Returns the `BYTES` bytes of the channels of the pixels, row by row
+    /// in RGB order, packed by pairs in three bytes, most significant bits
+    /// first. Channels are limited to `HDR_MAX`.
+    pub fn packed(&self) -> impl Iterator<Item = u8> + '_ {
+        let mut channels = self.0.iter().flatten().flat_map(|pixel| [pixel.r, pixel.g, pixel.b]).map(|x| x.min(HDR_MAX));
+        core::iter::from_fn(move || {
+            let (a, b) = (channels.next()?, channels.next().unwrap_or(0));
+            Some([(a >> 4) as u8, (a << 4 | b >> 8) as u8, b as u8])
+        })
+        .flatten()
+    }
+
+    /// Set the bits of the channels held by byte `index` of `packed()`, so
+    /// that an image is received byte by byte. Bytes past `BYTES` are
+    /// ignored.
+    pub fn set_packed(&mut self, index: usize, byte: u8) {
+        let (first, byte) = (index / 3 * 2, byte as u16);
+        let mut set = |n: usize, mask: u16, bits: u16| {
+            if let Some(channel) = self.channel_mut(n) {
+                *channel = *channel & HDR_MAX & !mask | bits;
+            }
+        };
+        match index % 3 {
+            0 => set(first, 0xff0, byte << 4),
+            1 => {
+                set(first, 0x00f, byte >> 4);
+                set(first + 1, 0xf00, (byte & 0xf) << 8);
+            }
+            _ => set(first + 1, 0x0ff, byte),
+        }
+    }
+
+    /// Returns channel `n`, counted row by row in RGB order.
+    fn channel_mut(&mut self, n: usize) -> Option<&mut u16> {
+        let pixel = (n / 3).checked_div(W).and_then(|row| self.0.get_mut(row)?.get_mut(n / 3 % W))?;
+        Some(match n % 3 {
+            0 => &mut pixel.r,
+            1 => &mut pixel.g,
+            _ => &mut pixel.b,
+        })
+    }
+}
+
+impl HdrImage {
+    /// Returns the packed bytes of the image, as `packed()` does.
+    pub fn to_bytes(&self) -> [u8; HDR_FRAME_LEN] {
+        let mut bytes = [0; HDR_FRAME_LEN];
+        for (byte, packed) in bytes.iter_mut().zip(self.packed()) {
+            *byte = packed;
+        }
+        bytes
+    }
+
+    /// Decode an image packed by `to_bytes()`.
+    pub fn from_bytes(bytes: &[u8; HDR_FRAME_LEN]) -> Self {
+        let mut image = HdrImage::default();
+        for (index, &byte) in bytes.iter().enumerate() {
+            image.set_packed(index, byte);
+        }
+        image
+    }
+}
+
+/// Gamma correction from 12-bit values to 12-bit output values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HdrGammaCurve([u16; 4096]);
+
+impl HdrGammaCurve {
+    /// No correction.
+    pub const IDENTITY: HdrGammaCurve = HdrGammaCurve::identity();
+
+    /// Power of 2.2, close to `gamma::gamma_correct()`.
+    pub const STANDARD: HdrGammaCurve = HdrGammaCurve::power(2.2);
+
+    /// Decoding of sRGB values to linear light.
+    pub const SRGB: HdrGammaCurve = HdrGammaCurve::srgb();
+
+    /// Create a curve applying no correction.
+    pub const fn identity() -> Self {
+        let mut table = [0; 4096];
+        let mut i = 0;
+        while i < 4096 {
+            table[i] = i as u16;
+            i += 1;
+        }
+        HdrGammaCurve(table)
+    }
+
+    /// Create a curve raising every normalized value to the power `gamma`.
+    pub const fn power(gamma: f32) -> Self {
+        let mut table = [0; 4096];
+        let mut i = 0;
+        while i < 4096 {
+            table[i] = to_hdr(gamma::pow(i as f64 / HDR_MAX as f64, gamma as f64));
+            i += 1;
+        }
+        HdrGammaCurve(table)
+    }
+
+    /// Create the sRGB decoding curve.
+    pub const fn srgb() -> Self {
+        let mut table = [0; 4096];
+        let mut i = 0;
+        while i < 4096 {
+            let c = i as f64 / HDR_MAX as f64;
+            let linear = if c <= 0.04045 { c / 12.92 } else { gamma::pow((c + 0.055) / 1.055, 2.4) };
+            table[i] = to_hdr(linear);
+            i += 1;
+        }
+        HdrGammaCurve(table)
+    }
+
+    /// Returns the curve matching a gamma preset.
+    pub fn from_preset(preset: Preset) -> &'static HdrGammaCurve {
+        match preset {
+            Preset::Linear => &HdrGammaCurve::IDENTITY,
+            Preset::Standard => &HdrGammaCurve::STANDARD,
+            Preset::Srgb => &HdrGammaCurve::SRGB,
+        }
+    }
+
+    /// Applies the correction to a value, limited to `HDR_MAX`.
+    pub fn correct(&self, x: u16) -> u16 {
+        self.0[x.min(HDR_MAX) as usize]
+    }
+}
+
+/// Scale a value between 0 and 1 to a rounded 12-bit value.
+const fn to_hdr(x: f64) -> u16 {
+    (x * HDR_MAX as f64 + 0.5) as u16
+}
+
+/// Fraction thresholds of the successive subframes, in bit-reversed order so
+/// that the rounded-up subframes are spread over the sequence.
+const THRESHOLDS: [u8; SUBFRAMES] = [0, 8, 4, 12, 2, 10, 6, 14, 1, 9, 5, 13, 3, 11, 7, 15];
+
+/// Start of the threshold sequence of every pixel of a 4×4 block, so that
+/// neighbouring pixels are not rounded up in the same subframes.
+const OFFSETS: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Returns the 8-bit output of the 12-bit output value `x` of the pixel at
+/// `row` and `col` during `subframe`.
+pub fn dither(x: u16, row: usize, col: usize, subframe: usize) -> u8 {
+    let x = x.min(HDR_MAX);
+    let (base, fraction) = ((x >> 4) as u8, (x & 0xf) as u8);
+    let threshold = THRESHOLDS[(subframe + OFFSETS[row % 4][col % 4] as usize) % SUBFRAMES];
+    if fraction > threshold {
+        base.saturating_add(1)
+    } else {
+        base
+    }
+}
+
+/// Gamma-correct a row of 12-bit pixels and return its 8-bit output during
+/// `subframe`.
+pub fn dither_row(pixels: &[HdrColor], row: usize, subframe: usize, curve: &HdrGammaCurve) -> [Color; 8] {
+    let mut out = [Color::default(); 8];
+    for (col, (pixel, out)) in pixels.iter().zip(out.iter_mut()).enumerate() {
+        let channel = |x: u16| dither(curve.correct(x), row, col, subframe);
+        *out = Color { r: channel(pixel.r), g: channel(pixel.g), b: channel(pixel.b) };
+    }
+    out
+}
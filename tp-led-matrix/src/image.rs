@@ -1,4 +1,5 @@
 use crate::gamma;
+#[cfg(not(feature = "std"))]
 use micromath::F32Ext;
 
 
@@ -6,11 +7,25 @@ use micromath::F32Ext;
 pub const RED: Color = Color {r: 0xff, g: 0x00, b: 0x00};
 pub const BLUE: Color = Color {r: 0x00, g: 0x00, b: 0xff};
 pub const GREEN: Color = Color {r: 0x00, g: 0xff, b: 0x00};
+pub const BLACK: Color = Color {r: 0x00, g: 0x00, b: 0x00};
+pub const WHITE: Color = Color {r: 0xff, g: 0xff, b: 0xff};
+pub const YELLOW: Color = Color {r: 0xff, g: 0xff, b: 0x00};
+pub const CYAN: Color = Color {r: 0x00, g: 0xff, b: 0xff};
+pub const MAGENTA: Color = Color {r: 0xff, g: 0x00, b: 0xff};
+pub const ORANGE: Color = Color {r: 0xff, g: 0xa5, b: 0x00};
+pub const PURPLE: Color = Color {r: 0x80, g: 0x00, b: 0x80};
+pub const PINK: Color = Color {r: 0xff, g: 0xc0, b: 0xcb};
+pub const GRAY: Color = Color {r: 0x80, g: 0x80, b: 0x80};
+pub const BROWN: Color = Color {r: 0xa5, g: 0x2a, b: 0x2a};
+pub const TEAL: Color = Color {r: 0x00, g: 0x80, b: 0x80};
+pub const NAVY: Color = Color {r: 0x00, g: 0x00, b: 0x80};
 
 
 #[derive(Clone)]
 #[derive(Copy)]
 #[derive(Default)]
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[repr(C)]
 /// represents an individual RGB pixel
 pub struct Color {
@@ -20,9 +35,13 @@ pub struct Color {
 }
 
 #[repr(transparent)]
-/// represents a whole 8×8 image made of pixels
-#[derive(Copy, Clone)]
-pub struct Image(pub [Color; 64]);
+/// represents a whole W×H image made of pixels, row by row. `Image` alone is
+/// the 8×8 image of one matrix.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Image<const W: usize = 8, const H: usize = 8>(pub [[Color; W]; H]);
+
+/// Image of a single 8×8 matrix.
+pub type Image8x8 = Image<8, 8>;
 
 impl core::ops::Mul<f32> for Color{
 type Output = Self;
@@ -43,34 +62,72 @@ fn div(self, rhs: f32) -> Self {
 }
 }
 
-impl core::ops::Index<(usize, usize)> for Image{
+impl core::ops::Add for Color {
+type Output = Self;
+/// Adds the channels, saturating at 255.
+fn add(self, rhs: Self) -> Self {
+    Color {
+        r: self.r.saturating_add(rhs.r),
+        g: self.g.saturating_add(rhs.g),
+        b: self.b.saturating_add(rhs.b),
+    }
+}
+}
+
+impl core::ops::Sub for Color {
+type Output = Self;
+/// Subtracts the channels, saturating at 0.
+fn sub(self, rhs: Self) -> Self {
+    Color {
+        r: self.r.saturating_sub(rhs.r),
+        g: self.g.saturating_sub(rhs.g),
+        b: self.b.saturating_sub(rhs.b),
+    }
+}
+}
+
+impl core::ops::Mul for Color {
+type Output = Self;
+/// Multiplies the channels as values between 0 and 1, rounding to the
+/// nearest.
+fn mul(self, rhs: Self) -> Self {
+    let f = |a: u8, b: u8| ((a as u16 * b as u16 + 127) / 255) as u8;
+    Color {
+        r: f(self.r, rhs.r),
+        g: f(self.g, rhs.g),
+        b: f(self.b, rhs.b),
+    }
+}
+}
+
+impl<const W: usize, const H: usize> core::ops::Index<(usize, usize)> for Image<W, H> {
 type Output = Color;
 fn index(&self, index: (usize, usize)) -> &Self::Output {
-    return &self.0[8*index.0+index.1];
+    &self.0[index.0][index.1]
 }
 }
 
-impl core::ops::IndexMut<(usize, usize)> for Image {
+impl<const W: usize, const H: usize> core::ops::IndexMut<(usize, usize)> for Image<W, H> {
 fn index_mut(&mut self, index: (usize, usize)) -> &mut Self::Output {
-    return &mut self.0[8*index.0+index.1];
+    &mut self.0[index.0][index.1]
 }
 }
 
-impl Default for Image {
+impl<const W: usize, const H: usize> Default for Image<W, H> {
 fn default() -> Self {
     Image::new_solid(Color::default())
 }
 }
 
-impl AsRef<[u8; 192]> for Image {
+impl AsRef<[u8; 192]> for Image8x8 {
 fn as_ref(&self) -> &[u8; 192] {
-    unsafe {return core::mem::transmute::<&Image, &[u8; 192]>(self)}
+    unsafe { core::mem::transmute::<&Image8x8, &[u8; 192]>(self) }
 }
 }
 
-impl AsMut<[u8; 192]> for Image {
+impl AsMut<[u8; 192]> for Image8x8 {
 fn as_mut(&mut self) -> &mut [u8; 192] {
-    unsafe {return core::mem::transmute::<&mut Image, &mut [u8; 192]>(self)}
+    unsafe { core::mem::transmute::<&mut Image8x8, &mut [u8; 192]>(self) }
 }
 }
 
@@ -81,28 +138,100 @@ impl Color {
     pub fn gamma_correct(&self) -> Self {
         return Color {r: gamma::gamma_correct(self.r), g: gamma::gamma_correct(self.g), b: gamma::gamma_correct(self.b)}
     }
+
+    /// Scales every channel by `scale`/255, rounding to the nearest. Gives
+    /// the same result as multiplying by `scale as f32 / 255.0`, without any
+    /// float operation.
+    pub const fn scale8(&self, scale: u8) -> Self {
+        Color {r: scale8(self.r, scale), g: scale8(self.g, scale), b: scale8(self.b, scale)}
+    }
+
+    /// Interpolates from `a` to `b` by `t`/255, without any float
+    /// operation.
+    pub const fn lerp8(a: Color, b: Color, t: u8) -> Self {
+        a.scale8(255 - t).add_const(b.scale8(t))
+    }
+
+    /// Saturating addition usable in const contexts.
+    const fn add_const(self, rhs: Color) -> Self {
+        Color {r: self.r.saturating_add(rhs.r), g: self.g.saturating_add(rhs.g), b: self.b.saturating_add(rhs.b)}
+    }
+
+    /// Interpolates linearly from `a` to `b`, `t` being limited to [0, 1].
+    pub fn lerp(a: Color, b: Color, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let f = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        Color {r: f(a.r, b.r), g: f(a.g, b.g), b: f(a.b, b.b)}
+    }
 }
 
-impl Image {
+/// Computes `x * scale / 255` rounded to the nearest, with a multiplication
+/// and shifts only.
+pub const fn scale8(x: u8, scale: u8) -> u8 {
+    let p = x as u16 * scale as u16 + 128;
+    ((p + (p >> 8)) >> 8) as u8
+}
+
+impl<const W: usize, const H: usize> Image<W, H> {
+    /// Number of columns.
+    pub const WIDTH: usize = W;
+
+    /// Number of rows.
+    pub const HEIGHT: usize = H;
+
+    /// Number of bytes of the pixels, in RGB order.
+    pub const BYTES: usize = W * H * 3;
+
     /// Creates a new image filled with one unique color.
-    pub fn new_solid(color: Color) -> Self {
-        return Image([color; 64])
+    pub const fn new_solid(color: Color) -> Self {
+        Image([[color; W]; H])
     }
 
     /// Returns reference on a row in an image.
     pub fn row(&self, row: usize) -> &[Color] {
-        assert!(row<8, "There's only 8 rows in the image");
-        return &self.0[row*8..(row+1)*8];
+        assert!(row<H, "There's only {} rows in the image", H);
+        &self.0[row]
     }
 
-    /// Creates a new image filled with a gradient of colors.
+    /// Returns every pixel, row by row.
+    pub fn pixels(&self) -> &[Color] {
+        // Rows are contiguous arrays of contiguous pixels
+        unsafe { core::slice::from_raw_parts(self.0.as_ptr() as *const Color, W * H) }
+    }
+
+    /// Returns every pixel, row by row, for modification.
+    pub fn pixels_mut(&mut self) -> &mut [Color] {
+        unsafe { core::slice::from_raw_parts_mut(self.0.as_mut_ptr() as *mut Color, W * H) }
+    }
+
+    /// Returns the pixels as bytes, row by row, in RGB order.
+    pub fn as_bytes(&self) -> &[u8] {
+        // Color is 3 bytes without padding
+        unsafe { core::slice::from_raw_parts(self.0.as_ptr() as *const u8, Self::BYTES) }
+    }
+
+    /// Returns the pixels as bytes, row by row, in RGB order, for
+    /// modification.
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        unsafe { core::slice::from_raw_parts_mut(self.0.as_mut_ptr() as *mut u8, Self::BYTES) }
+    }
+
+    /// Creates a new image filled with a gradient of colors: every pixel is
+    /// `color` divided by 1 + row² + col, rounded to the nearest.
     pub fn gradient(color: Color) -> Self {
-        let mut new_image: Image = Image::new_solid(color);
-        for row in 0..8 {
-            for col in 0..8 {
-                new_image[(row, col)] = color/(1 + row * row + col) as f32;
+        let mut new_image: Self = Image::new_solid(color);
+        for row in 0..H {
+            for col in 0..W {
+                let d = (1 + row * row + col).min(u16::MAX as usize / 2) as u16;
+                let f = |x: u8| ((2 * x as u16 + d) / (2 * d)) as u8;
+                new_image[(row, col)] = Color {r: f(color.r), g: f(color.g), b: f(color.b)};
             }
         }
         new_image
     }
+
+    /// Scales every pixel with `Color::scale8()`.
+    pub fn scale8(&self, scale: u8) -> Self {
+        Image(self.0.map(|row| row.map(|pixel| pixel.scale8(scale))))
+    }
 }
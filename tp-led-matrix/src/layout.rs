@@ -0,0 +1,122 @@
+//! Wiring and mounting of a panel, so that the same `Image` looks the same
+//! on every panel revision.
+//!
+//! The orientation (rotation then mirroring) is applied to whole images by
+//! `PanelLayout::orient()`. The column order, channel order and row pins
+//! are applied by the driver to every row it sends.
+
+use crate::hdr::HdrImage;
+use crate::{Color, Image};
+
+/// Clockwise rotation of the panel content.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Rotation {
+    R0,
+    R90,
+    R180,
+    R270,
+}
+
+/// Order in which the columns of a row are shifted into the DM163.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ColumnOrder {
+    /// Column 0 first.
+    Forward,
+    /// Column 7 first.
+    Reversed,
+}
+
+/// Order in which the channels of a pixel are shifted into the DM163.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ChannelOrder {
+    Rgb,
+    Bgr,
+    Grb,
+}
+
+/// Description of how a panel is wired and mounted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PanelLayout {
+    pub rotation: Rotation,
+    /// Mirror the content left to right, after the rotation.
+    pub mirror: bool,
+    pub column_order: ColumnOrder,
+    pub channel_order: ChannelOrder,
+    /// Index of the row pin, from C0 to C7, switched on for every row of
+    /// the image.
+    pub row_pins: [u8; 8],
+}
+
+impl Default for PanelLayout {
+    fn default() -> Self {
+        PanelLayout::DEFAULT
+    }
+}
+
+impl PanelLayout {
+    /// Layout of the panel of the STM32L475 IoT node: no rotation, columns
+    /// shifted from the last one in B, G, R order, row n on pin Cn.
+    pub const DEFAULT: PanelLayout = PanelLayout {
+        rotation: Rotation::R0,
+        mirror: false,
+        column_order: ColumnOrder::Reversed,
+        channel_order: ChannelOrder::Bgr,
+        row_pins: [0, 1, 2, 3, 4, 5, 6, 7],
+    };
+
+    /// Returns whether `row_pins` is a permutation of the 8 row pins.
+    pub fn is_valid(&self) -> bool {
+        (0..8).all(|pin| self.row_pins.contains(&pin))
+    }
+
+    /// Returns `image` as it must be sent for the panel to show it upright.
+    pub fn orient(&self, image: &Image) -> Image {
+        let rotated = match self.rotation {
+            Rotation::R0 => *image,
+            Rotation::R90 => image.rotate90(),
+            Rotation::R180 => image.rotate180(),
+            Rotation::R270 => image.rotate270(),
+        };
+        if self.mirror { rotated.flip_horizontal() } else { rotated }
+    }
+
+    /// Returns the 12-bit `image` as it must be sent, like `orient()`.
+    pub fn orient_hdr(&self, image: &HdrImage) -> HdrImage {
+        let rotated = match self.rotation {
+            Rotation::R0 => *image,
+            Rotation::R90 => image.rotate90(),
+            Rotation::R180 => image.rotate180(),
+            Rotation::R270 => image.rotate270(),
+        };
+        if self.mirror { rotated.flip_horizontal() } else { rotated }
+    }
+
+    /// Returns the bytes of a row of pixels in the order they are shifted
+    /// into the DM163.
+    pub fn row_data(&self, pixels: &[Color; 8]) -> [u8; 24] {
+        self.shift_order(&pixels.map(|pixel| [pixel.r, pixel.g, pixel.b]))
+    }
+
+    /// Returns the red, green and blue values of every column in the order
+    /// they are shifted into the DM163, for the pixel data of bank 1 as
+    /// well as the dot correction of bank 0.
+    pub fn shift_order(&self, columns: &[[u8; 3]; 8]) -> [u8; 24] {
+        let mut data = [0; 24];
+        for (i, values) in data.chunks_exact_mut(3).enumerate() {
+            let [r, g, b] = match self.column_order {
+                ColumnOrder::Forward => columns[i],
+                ColumnOrder::Reversed => columns[7 - i],
+            };
+            values.copy_from_slice(&match self.channel_order {
+                ChannelOrder::Rgb => [r, g, b],
+                ChannelOrder::Bgr => [b, g, r],
+                ChannelOrder::Grb => [g, r, b],
+            });
+        }
+        data
+    }
+}
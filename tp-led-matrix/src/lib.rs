@@ -1,11 +1,46 @@
-#![no_std]
+//! Driver and image handling for the 8×8 LED matrix.
+//!
+//! `image`, `color`, `blend`, `draw`, `font`, `transform`, `effect`, `hdr`,
+//! `gamma`, `dot_correction`, `protocol`, `command`, `tile` and the generic
+//! `matrix` driver with its `shift` backends and panel `layout` are hardware
+//! independent and build on the host (`cargo host-test`). The STM32L475
+//! pinout of the driver needs the `hw-stm32l475` feature, which is enabled
+//! by default. With the `embedded-graphics` feature, `graphics` makes
+//! `Image` a draw target of the embedded-graphics crates.
+//!
+//! With the `std` feature, `trace` records the pin activity of the driver on
+//! the host for inspection in GTKWave or golden-trace tests, and `anim`
+//! reads and writes animation files. The host tools in `src/bin`
+//! (`cargo host-run led-sim`, `cargo host-run led-send`,
+//! `cargo host-run led-convert`) and their `tty` and `convert` modules need
+//! the `host-tools` feature.
+
+#![cfg_attr(not(feature = "std"), no_std)]
 
 pub mod image;
+pub mod color;
+pub mod blend;
+pub mod draw;
+pub mod font;
+pub mod transform;
+pub mod effect;
+pub mod tile;
 pub mod matrix;
+pub mod layout;
 pub mod gamma;
-pub use image::{Color, Image};
-
-
-
-
-
+pub mod hdr;
+pub mod protocol;
+pub mod command;
+pub mod dot_correction;
+pub mod shift;
+#[cfg(feature = "embedded-graphics")]
+pub mod graphics;
+#[cfg(feature = "std")]
+pub mod trace;
+#[cfg(feature = "std")]
+pub mod anim;
+#[cfg(feature = "host-tools")]
+pub mod tty;
+#[cfg(feature = "host-tools")]
+pub mod convert;
+pub use image::{Color, Image, Image8x8};
@@ -1,11 +1,77 @@
 #![no_std]
 #![no_main]
-#[rtic::app(device = pac, dispatchers = [USART2])]
+
+use core::sync::atomic::{AtomicU32, Ordering};
+use heapless::pool::{Box, Pool};
+use stm32l4xx_hal::flash::FlashPage;
+use tp_led_matrix::command::Status;
+use tp_led_matrix::hdr::HdrImage;
+use tp_led_matrix::layout::PanelLayout;
+use tp_led_matrix::protocol::{Checksum, Framing};
+use tp_led_matrix::Image;
+
+// Framing and checksum expected on USART1, selected at build time. Commands
+// are only received with the stuffed framing, legacy files keep working
+// without it.
+#[cfg(not(feature = "se203-v2"))]
+const FRAMING: Framing = Framing::Legacy;
+#[cfg(feature = "se203-v2")]
+const FRAMING: Framing = Framing::Stuffed;
+#[cfg(not(feature = "se203-crc"))]
+const CHECKSUM: Checksum = Checksum::None;
+#[cfg(feature = "se203-crc")]
+const CHECKSUM: Checksum = Checksum::Crc16;
+
+// Wiring and mounting of the panel.
+const LAYOUT: PanelLayout = PanelLayout::DEFAULT;
+
+// Backend shifting the rows out, named in the cycle reports.
+#[cfg(not(feature = "spi-dma"))]
+const BACKEND: &str = "bit-bang";
+#[cfg(feature = "spi-dma")]
+const BACKEND: &str = "SPI1+DMA";
+
+// Most CPU cycles spent latching a row at the end of a DMA transfer since
+// the last report of the display task.
+static LATCH_CYCLES: AtomicU32 = AtomicU32::new(0);
+
+// Last page of the flash, left out of the program by memory.x, where the
+// white balance is kept across resets.
+const WHITE_BALANCE_PAGE: FlashPage = FlashPage(511);
+
+/// Message sent back to the user on USART1.
+#[derive(Clone, Copy)]
+pub enum Reply {
+    /// `ACK` or `NACK` after a frame.
+    Byte(u8),
+    /// Answer to `QueryStatus`.
+    Status(Status),
+}
+
+/// Replace the image waiting to be displayed, if any, by `image`.
+fn show(next_image: &mut Option<Box<Image>>, next_hdr: &mut Option<HdrImage>, pool: &mut Pool<Image>, image: Image) {
+    next_hdr.take();
+    if let Some(old) = next_image.take() {
+        pool.free(old);
+    }
+    next_image.replace(pool.alloc().unwrap().init(image));
+}
+
+#[rtic::app(device = pac, dispatchers = [USART2, USART3])]
 
 mod app {
     use stm32l4xx_hal::device::USART1;
-    use tp_led_matrix::{Image, Color, matrix::Matrix, image};
+    use tp_led_matrix::{Image, Color, image, protocol::{self, Decoder, Packet}};
+    #[cfg(not(feature = "spi-dma"))]
+    use tp_led_matrix::matrix::Stm32l475Matrix as Panel;
+    #[cfg(feature = "spi-dma")]
+    use tp_led_matrix::matrix::Stm32l475SpiMatrix as Panel;
+    use tp_led_matrix::command::{Command, DisplayMode, Settings, Status};
+    use tp_led_matrix::dot_correction::{WhiteBalance, WHITE_BALANCE_RECORD_LEN};
+    use tp_led_matrix::gamma::Preset;
+    use tp_led_matrix::hdr::{HdrGammaCurve, DITHERED_FPS, SUBFRAMES};
     use cortex_m_rt::entry;
+    use cortex_m::peripheral::DWT;
     use core::mem::MaybeUninit;
     use panic_probe as _;
     use heapless::pool::*;
@@ -13,7 +79,8 @@ mod app {
     use dwt_systick_monotonic::ExtU32;
     use defmt_rtt as _;
     use stm32l4xx_hal::{pac, prelude::*};   // Just to link it in the executable (it provides the vector table)
-    use stm32l4xx_hal::serial::{Config, Event, Rx, Serial};
+    use stm32l4xx_hal::serial::{Config, Event, Rx, Serial, Tx};
+    use stm32l4xx_hal::flash::{WriteErase, CR, KEYR, SR};
     use super::*;
 
     #[monotonic(binds = SysTick, default = true)]
@@ -22,16 +89,24 @@ mod app {
 
     #[shared]
     struct Shared {
+        #[lock_free]
+        matrix: Panel, //only used by the tasks of the highest priority
         next_image: Option<Box<Image>>, //next image to be displayed
-        pool: Pool<Image>
+        next_hdr: Option<HdrImage>, //next 12-bit image, if received after next_image
+        pool: Pool<Image>,
+        settings: Settings //display settings changed by commands
     }
 
     #[local]
     struct Local {
-        matrix: Matrix,
         usart1_rx: Rx<USART1>,
+        usart1_tx: Tx<USART1>,
         current_image: Box<Image>, //image to be displayed
-        rx_image: Box<Image> //image sent by the user
+        current_hdr: HdrImage, //image displayed in the dithered mode, current_image widened or received with 12 bits
+        decoder: Decoder, //SE203 frames sent by the user
+        flash_keyr: KEYR, //flash registers to save the white balance
+        flash_sr: SR,
+        flash_cr: CR
     }
 
     #[idle(local = [])]
@@ -40,54 +115,192 @@ mod app {
         loop {}
     }
 
-    #[task(local = [current_image, matrix, next_row: usize = 0], shared = [next_image, pool], priority = 2)]
-    //Displays the current image
+    #[task(local = [current_image, current_hdr, next_row: usize = 0, subframe: usize = 0,
+                    gamma: Option<Preset> = None, mode: DisplayMode = DisplayMode::Standard,
+                    max_cycles: u32 = 0, frames: u32 = 0],
+           shared = [matrix, next_image, next_hdr, pool, settings], priority = 3)]
+    //Displays the current image, above every other task so that bursts of
+    //serial bytes never delay a row
     fn display(mut cx: display::Context, at: Instant) {
-        cx.local.matrix.send_row(*cx.local.next_row, cx.local.current_image.row(*cx.local.next_row));
+        let row = *cx.local.next_row;
+        let matrix = cx.shared.matrix;
+        // The DWT cycle counter is enabled by the monotonic timer
+        let start = DWT::cycle_count();
+        match *cx.local.mode {
+            DisplayMode::Standard => matrix.start_row(row, cx.local.current_image.row(row)),
+            DisplayMode::Dithered => matrix.start_hdr_row(row, cx.local.current_hdr.row(row), *cx.local.subframe),
+        }
+        // With SPI1+DMA, row_sent latches the row at the end of the transfer
+        #[cfg(not(feature = "spi-dma"))]
+        matrix.finish_row();
+        *cx.local.max_cycles = (*cx.local.max_cycles).max(DWT::cycle_count().wrapping_sub(start));
        // Increment next_line up to 7 and wraparound to 0
-        if *cx.local.next_row == 7 {
-            (cx.shared.next_image, cx.shared.pool).lock(|next_image, pool| {
+        if row == 7 {
+            let settings = cx.shared.settings.lock(|settings| *settings);
+            // Bank 0 is only written again when the dot correction changes
+            matrix.set_white_balance(settings.white_balance);
+            matrix.set_brightness(settings.brightness);
+            // Copy the curve tables only when the preset changes
+            if *cx.local.gamma != Some(settings.gamma) {
+                matrix.set_gamma(settings.gamma.curve());
+                matrix.set_hdr_gamma(HdrGammaCurve::from_preset(settings.gamma));
+                *cx.local.gamma = Some(settings.gamma);
+            }
+            *cx.local.mode = settings.mode;
+            *cx.local.subframe = (*cx.local.subframe + 1) % SUBFRAMES;
+            // Report the CPU cost of the shift-out backend every 60 frames,
+            // latching included, to compare the backends
+            *cx.local.frames += 1;
+            if *cx.local.frames >= 60 {
+                let cycles = *cx.local.max_cycles + LATCH_CYCLES.swap(0, Ordering::Relaxed);
+                defmt::debug!("{}: row sent in at most {} cycles", BACKEND, cycles);
+                *cx.local.frames = 0;
+                *cx.local.max_cycles = 0;
+            }
+            let current_image = &mut *cx.local.current_image;
+            let (swapped, hdr) = (cx.shared.next_image, cx.shared.next_hdr, cx.shared.pool).lock(|next_image, next_hdr, pool| {
                 if let Some(mut t) = next_image.take() {
-                    core::mem::swap(&mut t, &mut *cx.local.current_image);
+                    core::mem::swap(&mut t, current_image);
                     pool.free(t);
-                }      
+                    return (true, None);
+                }
+                (false, next_hdr.take())
             });
+            // Orient the image once, rows are then sent as they are
+            if swapped {
+                let oriented = matrix.layout().orient(&**cx.local.current_image);
+                **cx.local.current_image = oriented;
+                *cx.local.current_hdr = HdrImage::from(&oriented);
+            }
+            if let Some(hdr) = hdr {
+                let oriented = matrix.layout().orient_hdr(&hdr);
+                **cx.local.current_image = oriented.to_image();
+                *cx.local.current_hdr = oriented;
+            }
         }
-        *cx.local.next_row = (*cx.local.next_row+1)%8;
-        display::spawn_at(at + 1.secs()/(8*60), at + 1.secs()/(8*60)).unwrap();
+        *cx.local.next_row = (row+1)%8;
+        let fps = match *cx.local.mode {
+            DisplayMode::Standard => 60,
+            DisplayMode::Dithered => DITHERED_FPS,
+        };
+        display::spawn_at(at + 1.secs()/(8*fps), at + 1.secs()/(8*fps)).unwrap();
+    }
+
+    #[cfg(feature = "spi-dma")]
+    #[task(binds = DMA1_CH3, shared = [matrix], priority = 3)]
+    //Latches the row whose data SPI1 has just sent, at the priority of the
+    //display which started it
+    fn row_sent(cx: row_sent::Context) {
+        let start = DWT::cycle_count();
+        cx.shared.matrix.shift_out().clear_interrupt();
+        cx.shared.matrix.finish_row();
+        LATCH_CYCLES.fetch_max(DWT::cycle_count().wrapping_sub(start), Ordering::Relaxed);
     }
 
     #[task(binds = USART1,
-        local = [usart1_rx, rx_image, next_pos: usize = 0],
-        shared = [next_image, pool])]
-    //Adds the bytes sent by the users to next_image
+        local = [usart1_rx, decoder],
+        shared = [next_image, next_hdr, pool, settings], priority = 2)]
+    //Adds the bytes sent by the users to next_image, and handles their commands.
+    //Runs below the display, but above the replies which block on TX
     fn receive_byte(mut cx: receive_byte::Context)
     {
         if let Ok(b) = cx.local.usart1_rx.read() {
-            // Handle the incoming byte according to the SE203 protocol
-            // and update next_image
-            // Do not forget that next_image.as_mut() might be handy here!
-            if b == 0xff {*cx.local.next_pos = 0;}
-            else {
-                cx.local.rx_image.as_mut()[*cx.local.next_pos] =  b;
-                *cx.local.next_pos += 1;
-            }
+            // Images received while paused are dropped
+            let shows_images = cx.shared.settings.lock(|settings| settings.shows_images());
+            // Handle the incoming byte according to the SE203 protocol.
             // If the received image is complete, make it available to
             // the display task.
-            if *cx.local.next_pos == 8 * 8 * 3 {
-                (cx.shared.next_image, cx.shared.pool).lock(|next_image, pool| {
-                    if let Some(mut image) = next_image.take() {
-                        pool.free(image);
+            match cx.local.decoder.push(b) {
+                Ok(Some(Packet::Image(image))) => {
+                    if shows_images {
+                        (cx.shared.next_image, cx.shared.next_hdr, cx.shared.pool).lock(|next_image, next_hdr, pool| show(next_image, next_hdr, pool, *image));
+                    }
+                    if CHECKSUM != Checksum::None {
+                        reply::spawn(Reply::Byte(protocol::ACK)).ok();
+                    }
+                }
+                // The last image received wins, whatever its precision
+                Ok(Some(Packet::Hdr(hdr))) => {
+                    if shows_images {
+                        (cx.shared.next_image, cx.shared.next_hdr, cx.shared.pool).lock(|next_image, next_hdr, pool| {
+                            if let Some(old) = next_image.take() {
+                                pool.free(old);
+                            }
+                            next_hdr.replace(*hdr);
+                        });
+                    }
+                    if CHECKSUM != Checksum::None {
+                        reply::spawn(Reply::Byte(protocol::ACK)).ok();
+                    }
+                }
+                Ok(Some(Packet::Command(command))) => match command {
+                    Command::Fill(color) if shows_images => {
+                        (cx.shared.next_image, cx.shared.next_hdr, cx.shared.pool).lock(|next_image, next_hdr, pool| show(next_image, next_hdr, pool, Image::new_solid(color)));
+                    }
+                    Command::Gradient(color) if shows_images => {
+                        (cx.shared.next_image, cx.shared.next_hdr, cx.shared.pool).lock(|next_image, next_hdr, pool| show(next_image, next_hdr, pool, Image::gradient(color)));
+                    }
+                    Command::QueryStatus => {
+                        let stats = cx.local.decoder.stats();
+                        let status = Status {
+                            settings: cx.shared.settings.lock(|settings| *settings),
+                            frames: stats.frames,
+                            errors: stats.errors(),
+                        };
+                        reply::spawn(Reply::Status(status)).ok();
+                    }
+                    Command::Reset => cortex_m::peripheral::SCB::sys_reset(),
+                    Command::SetWhiteBalance(white_balance) => {
+                        cx.shared.settings.lock(|settings| settings.apply(&command));
+                        save_white_balance::spawn(white_balance).ok();
+                    }
+                    _ => {
+                        cx.shared.settings.lock(|settings| settings.apply(&command));
                     }
-                    let mut future_image = pool.alloc().unwrap().init(Image::default());
-                    core::mem::swap(&mut future_image, &mut *cx.local.rx_image);
-                    next_image.replace(future_image);     
-                }); 
-                *cx.local.next_pos = 0;
+                },
+                Ok(None) => {}
+                // Extra bytes after a frame which has already been acknowledged
+                Err(e @ protocol::Error::Overrun) => defmt::warn!("SE203 error: {}", e),
+                Err(e) => {
+                    defmt::warn!("SE203 error: {}", e);
+                    if CHECKSUM != Checksum::None {
+                        reply::spawn(Reply::Byte(protocol::NACK)).ok();
+                    }
+                }
             }
         }
     }
 
+    #[task(local = [usart1_tx], capacity = 4, priority = 1)]
+    //Sends a reply to the user, at a lower priority than the reception
+    fn reply(cx: reply::Context, reply: Reply) {
+        let tx = cx.local.usart1_tx;
+        match reply {
+            Reply::Byte(b) => {
+                nb::block!(tx.write(b)).ok();
+            }
+            Reply::Status(status) => {
+                nb::block!(tx.write(protocol::CMD)).ok();
+                for b in status.to_bytes() {
+                    nb::block!(tx.write(b)).ok();
+                }
+            }
+        }
+    }
+
+    #[task(local = [flash_keyr, flash_sr, flash_cr], capacity = 1, priority = 1)]
+    //Saves the white balance in flash, so that init restores it after a reset
+    fn save_white_balance(cx: save_white_balance::Context, white_balance: WhiteBalance) {
+        // The page is in the second bank, so erasing it does not stall the
+        // code running from the first one
+        let result = cx.local.flash_keyr.unlock_flash(cx.local.flash_sr, cx.local.flash_cr).and_then(|mut flash| {
+            flash.erase_page(WHITE_BALANCE_PAGE)?;
+            flash.write(WHITE_BALANCE_PAGE.to_address(), &white_balance.to_record())
+        });
+        if let Err(e) = result {
+            defmt::warn!("white balance not saved: {}", defmt::Debug2Format(&e));
+        }
+    }
 
     #[init]
     //Initializes the hardware and creates an empty image
@@ -112,7 +325,8 @@ mod app {
         let mut gpioa = dp.GPIOA.split(&mut rcc.ahb2);
         let mut gpiob = dp.GPIOB.split(&mut rcc.ahb2);
         let mut gpioc = dp.GPIOC.split(&mut rcc.ahb2);
-        let mut matrix = Matrix::new(
+        #[cfg(not(feature = "spi-dma"))]
+        let mut matrix = Panel::new(
             gpioa.pa2,
             gpioa.pa3,
             gpioa.pa4,
@@ -133,13 +347,47 @@ mod app {
             &mut gpioc.moder,
             &mut gpioc.otyper,
             clocks);        
+        #[cfg(feature = "spi-dma")]
+        let mut matrix = Panel::new(
+            gpioa.pa2,
+            gpioa.pa3,
+            gpioa.pa5,
+            gpioa.pa6,
+            gpioa.pa7,
+            gpioa.pa15,
+            gpiob.pb0,
+            gpiob.pb2,
+            gpiob.pb3,
+            gpiob.pb5,
+            gpioc.pc3,
+            gpioc.pc4,
+            gpioc.pc5,
+            &mut gpioa.moder,
+            &mut gpioa.otyper,
+            &mut gpiob.moder,
+            &mut gpiob.otyper,
+            &mut gpiob.afrl,
+            &mut gpioc.moder,
+            &mut gpioc.otyper,
+            dp.SPI1,
+            dp.DMA1,
+            cortex_m::singleton!(: [u8; tp_led_matrix::shift::DMA_BUFFER_LEN] = [0; tp_led_matrix::shift::DMA_BUFFER_LEN]).unwrap(),
+            clocks);
+        matrix.set_layout(LAYOUT);
             
         let rx = gpiob.pb7.into_alternate::<7>(&mut gpiob.moder,&mut gpiob.otyper,&mut gpiob.afrl);
         let tx = gpiob.pb6.into_alternate::<7>(&mut gpiob.moder,&mut gpiob.otyper,&mut gpiob.afrl);
         let config = stm32l4xx_hal::serial::Config::default().baudrate(38400.bps());
         let mut serial = stm32l4xx_hal::serial::Serial::usart1(dp.USART1, (tx, rx), config, clocks, &mut rcc.apb2);
         serial.listen(Event::Rxne);
-        let usart1_rx = serial.split().1;
+        let (usart1_tx, usart1_rx) = serial.split();
+
+        // Restore the white balance saved by save_white_balance, if any
+        let record = unsafe { &*(WHITE_BALANCE_PAGE.to_address() as *const [u8; WHITE_BALANCE_RECORD_LEN]) };
+        let mut settings = Settings::default();
+        if let Some(white_balance) = WhiteBalance::from_record(record) {
+            settings.white_balance = white_balance;
+        }
         //*cx.next_image = Image::Default();
         let pool: Pool<Image> = Pool::new();
         unsafe {
@@ -147,11 +395,9 @@ mod app {
             pool.grow_exact(&mut MEMORY);   // static mut access is unsafe
         }
         let mut current_image = pool.alloc().unwrap().init(Image::default());
-        let mut rx_image = pool.alloc().unwrap().init(Image::default());
         display::spawn(mono.now()).unwrap();
 
         // Return the resources and the monotonic timer
-        (Shared {next_image: None, pool}, Local { matrix, usart1_rx, current_image, rx_image}, init::Monotonics(mono))
+        (Shared {matrix, next_image: None, next_hdr: None, pool, settings}, Local { usart1_rx, usart1_tx, current_image, current_hdr: HdrImage::default(), decoder: Decoder::with_checksum(FRAMING, CHECKSUM), flash_keyr: flash.keyr, flash_sr: flash.sr, flash_cr: flash.cr}, init::Monotonics(mono))
     }
 }
-
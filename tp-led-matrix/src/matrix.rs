@@ -1,157 +1,440 @@
-use stm32l4xx_hal::delay::DelayCM;
-use stm32l4xx_hal::prelude::_embedded_hal_blocking_delay_DelayMs;
-use stm32l4xx_hal::{gpio::*, rcc::Clocks};
-
-use crate::{Image, Color, gamma};
-
-pub struct Matrix {
-    sb: PC5<Output<PushPull>>,
-    lat: PC4<Output<PushPull>>,
-    rst: PC3<Output<PushPull>>,
-    sck: PB1<Output<PushPull>>,
-    sda: PA4<Output<PushPull>>,
-    c0: PB2<Output<PushPull>>,
-    c1: PA15<Output<PushPull>>,
-    c2: PA2<Output<PushPull>>,
-    c3: PA7<Output<PushPull>>,
-    c4: PA6<Output<PushPull>>,
-    c5: PA5<Output<PushPull>>,
-    c6: PB0<Output<PushPull>>,
-    c7: PA3<Output<PushPull>>,
+use core::convert::Infallible;
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::digital::v2::{OutputPin, PinState};
+
+use crate::{Image, Color};
+use crate::dot_correction::{DotCorrection, WhiteBalance};
+use crate::gamma::GammaCurve;
+use crate::hdr::{self, HdrColor, HdrGammaCurve};
+use crate::layout::PanelLayout;
+use crate::shift::{BitBang, ShiftOut};
+
+/// Driver for the DM163 shift registers and the eight row pins.
+///
+/// Every pin only needs to implement `OutputPin`, so the same sequencing runs
+/// on the board and against mock pins on the host. `ROW` is the type shared by
+/// the eight row pins C0 to C7. The SCK and SDA data are sent by the `OUT`
+/// backend, either by bit-banging the pins or through a SPI peripheral. How
+/// rows are shifted and switched on follows a `PanelLayout`.
+///
+/// `send_row()` waits for the backend. With a backend shifting in the
+/// background, `start_row()` returns at once and `finish_row()`, called when
+/// the transfer is over, latches the row.
+pub struct Matrix<SB, LAT, RST, OUT, ROW> {
+    sb: SB,
+    lat: LAT,
+    rst: RST,
+    out: OUT,
+    rows: [ROW; 8],
+    brightness: u8,
+    /// Dot correction at full brightness
+    calibration: DotCorrection,
+    /// Dot correction latched into bank 0
+    dot_correction: DotCorrection,
+    gamma: GammaCurve,
+    hdr_gamma: &'static HdrGammaCurve,
+    layout: PanelLayout,
+    /// Row started by `start_row()` and not latched yet
+    pending: Option<usize>,
 }
 
-impl Matrix {
-    /// Create a new matrix from the control registers and the individual
-    /// unconfigured pins. SB and LAT will be set high by default, while
-    /// other pins will be set low. After 100ms, RST will be set high, and
-    /// the bank 0 will be initialized by calling `init_bank0()` on the
-    /// newly constructed structure.
-    /// The pins will be set to very high speed mode.
-    #[allow(clippy::too_many_arguments)]   // Necessary to avoid a clippy warning
-    pub fn new(
-        pa2: PA2<Analog>,
-        pa3: PA3<Analog>,
-        pa4: PA4<Analog>,
-        pa5: PA5<Analog>,
-        pa6: PA6<Analog>,
-        pa7: PA7<Analog>,
-        pa15: PA15<Alternate<PushPull, 0>>,
-        pb0: PB0<Analog>,
-        pb1: PB1<Analog>,
-        pb2: PB2<Analog>,
-        pc3: PC3<Analog>,
-        pc4: PC4<Analog>,
-        pc5: PC5<Analog>,
-        gpioa_moder: &mut MODER<'A'>,
-        gpioa_otyper: &mut OTYPER<'A'>,
-        gpiob_moder: &mut MODER<'B'>,
-        gpiob_otyper: &mut OTYPER<'B'>,
-        gpioc_moder: &mut MODER<'C'>,
-        gpioc_otyper: &mut OTYPER<'C'>,
-        clocks: Clocks,
+impl<SB, LAT, RST, SCK, SDA, ROW> Matrix<SB, LAT, RST, BitBang<SCK, SDA>, ROW>
+where
+    SB: OutputPin<Error = Infallible>,
+    LAT: OutputPin<Error = Infallible>,
+    RST: OutputPin<Error = Infallible>,
+    SCK: OutputPin<Error = Infallible>,
+    SDA: OutputPin<Error = Infallible>,
+    ROW: OutputPin<Error = Infallible>,
+{
+    /// Create a new matrix from already configured output pins, bit-banging
+    /// SCK and SDA, and initialize it as `with_shift_out()` does.
+    pub fn from_pins<D: DelayMs<u8>>(
+        sb: SB,
+        lat: LAT,
+        rst: RST,
+        sck: SCK,
+        sda: SDA,
+        rows: [ROW; 8],
+        delay: &mut D,
+    ) -> Self {
+        Matrix::with_shift_out(sb, lat, rst, BitBang::new(sck, sda), rows, delay)
+    }
+}
+
+impl<SB, LAT, RST, OUT, ROW> Matrix<SB, LAT, RST, OUT, ROW>
+where
+    SB: OutputPin<Error = Infallible>,
+    LAT: OutputPin<Error = Infallible>,
+    RST: OutputPin<Error = Infallible>,
+    OUT: ShiftOut,
+    ROW: OutputPin<Error = Infallible>,
+{
+    /// Create a new matrix from already configured output pins and a
+    /// backend for SCK and SDA. SB and LAT will be set high, while other
+    /// pins will be set low. After 100ms, RST will be set high, and the bank
+    /// 0 will be initialized by calling `init_bank0()` on the newly
+    /// constructed structure.
+    pub fn with_shift_out<D: DelayMs<u8>>(
+        sb: SB,
+        lat: LAT,
+        rst: RST,
+        out: OUT,
+        rows: [ROW; 8],
+        delay: &mut D,
     ) -> Self {
-        let mut matrix:Matrix = Matrix { 
-            sb: pc5.into_push_pull_output_in_state(gpioc_moder, gpioc_otyper, PinState::High).set_speed(Speed::VeryHigh), 
-            lat: pc4.into_push_pull_output_in_state(gpioc_moder, gpioc_otyper, PinState::High).set_speed(Speed::VeryHigh),
-            rst: pc3.into_push_pull_output_in_state(gpioc_moder, gpioc_otyper, PinState::Low).set_speed(Speed::VeryHigh),
-            sck: pb1.into_push_pull_output_in_state(gpiob_moder, gpiob_otyper, PinState::Low).set_speed(Speed::VeryHigh),
-            sda: pa4.into_push_pull_output_in_state(gpioa_moder, gpioa_otyper, PinState::Low).set_speed(Speed::VeryHigh),
-            c0: pb2.into_push_pull_output_in_state(gpiob_moder, gpiob_otyper, PinState::Low).set_speed(Speed::VeryHigh),
-            c1: pa15.into_push_pull_output_in_state(gpioa_moder, gpioa_otyper, PinState::Low).set_speed(Speed::VeryHigh),
-            c2: pa2.into_push_pull_output_in_state(gpioa_moder, gpioa_otyper, PinState::Low).set_speed(Speed::VeryHigh),
-            c3: pa7.into_push_pull_output_in_state(gpioa_moder, gpioa_otyper, PinState::Low).set_speed(Speed::VeryHigh),
-            c4: pa6.into_push_pull_output_in_state(gpioa_moder, gpioa_otyper, PinState::Low).set_speed(Speed::VeryHigh),
-            c5: pa5.into_push_pull_output_in_state(gpioa_moder, gpioa_otyper, PinState::Low).set_speed(Speed::VeryHigh),
-            c6: pb0.into_push_pull_output_in_state(gpiob_moder, gpiob_otyper, PinState::Low).set_speed(Speed::VeryHigh),
-            c7: pa3.into_push_pull_output_in_state(gpioa_moder, gpioa_otyper, PinState::Low).set_speed(Speed::VeryHigh) 
+        let mut matrix = Matrix {
+            sb,
+            lat,
+            rst,
+            out,
+            rows,
+            brightness: 255,
+            calibration: DotCorrection::FULL,
+            dot_correction: DotCorrection::FULL,
+            gamma: GammaCurve::STANDARD,
+            hdr_gamma: &HdrGammaCurve::STANDARD,
+            layout: PanelLayout::DEFAULT,
+            pending: None,
         };
-        let mut delay:DelayCM = DelayCM::new(clocks);
+        matrix.sb.set_high().ok();
+        matrix.lat.set_high().ok();
+        matrix.rst.set_low().ok();
+        for row in matrix.rows.iter_mut() {
+            row.set_low().ok();
+        }
         delay.delay_ms(100u8);
-        matrix.rst.set_high();
+        matrix.rst.set_high().ok();
         matrix.init_bank0();
         matrix
     }
 
-    /// Make a brief high pulse of the SCK pin
-    fn pulse_sck(&mut self) {
-        self.sck.set_high();
-        self.sck.set_low();
+    /// Scale the output current of every channel by `brightness`/255, through
+    /// the dot correction. The 6-bit dot correction gives 64 steps, without
+    /// reducing the precision of the pixel data.
+    pub fn set_brightness(&mut self, brightness: u8) {
+        self.brightness = brightness;
+        self.update_bank0();
+    }
+
+    /// Replace the dot correction used at full brightness, for example to
+    /// even out the columns, and latch it scaled by the current brightness.
+    pub fn set_dot_correction(&mut self, calibration: DotCorrection) {
+        self.calibration = calibration;
+        self.update_bank0();
+    }
+
+    /// Replace the dot correction used at full brightness by the one of a
+    /// white balance calibration.
+    pub fn set_white_balance(&mut self, white_balance: WhiteBalance) {
+        self.set_dot_correction(white_balance.dot_correction());
+    }
+
+    /// Replace the gamma correction applied to every pixel, which is
+    /// `GammaCurve::STANDARD` initially.
+    pub fn set_gamma(&mut self, gamma: GammaCurve) {
+        self.gamma = gamma;
+    }
+
+    /// Replace the gamma correction applied to 12-bit pixels, which is
+    /// `HdrGammaCurve::STANDARD` initially.
+    pub fn set_hdr_gamma(&mut self, gamma: &'static HdrGammaCurve) {
+        self.hdr_gamma = gamma;
     }
 
     /// Make a brief low pulse of the LAT pin
     fn pulse_lat(&mut self) {
-        self.lat.set_low();
-        self.lat.set_high();
+        self.lat.set_low().ok();
+        self.lat.set_high().ok();
     }
 
-    /// Set the given row output in the chosen state
+    /// Set the pin of the given row in the chosen state
     fn row(&mut self, row: usize, state: PinState) {
-        match row {
-            0=>self.c0.set_state(state),
-            1=>self.c1.set_state(state),
-            2=>self.c2.set_state(state),
-            3=>self.c3.set_state(state),
-            4=>self.c4.set_state(state),
-            5=>self.c5.set_state(state),
-            6=>self.c6.set_state(state),
-            7=>self.c7.set_state(state),
-            _=>panic!()
+        self.rows[self.layout.row_pins[row] as usize].set_state(state).ok();
+    }
+
+    /// Replace the layout of the panel, which is `PanelLayout::DEFAULT`
+    /// initially, and shift the dot correction again in its order. Every
+    /// row is switched off until the next one is sent.
+    ///
+    /// # Panics
+    ///
+    /// If the row pins of the layout are not a permutation of the 8 pins.
+    pub fn set_layout(&mut self, layout: PanelLayout) {
+        assert!(layout.is_valid(), "row pins must be a permutation of C0 to C7");
+        self.finish_row();
+        for row in self.rows.iter_mut() {
+            row.set_low().ok();
         }
+        self.layout = layout;
+        self.init_bank0();
     }
 
-    /// Send a byte on SDA starting with the MSB and pulse SCK high after each bit
-    fn send_byte(&mut self, pixel: u8) {
-        let mut i=7;
-        while i>=0 {
-            match pixel>>i & 1 {
-                0=>self.sda.set_low(),
-                _=>self.sda.set_high()
+    /// Returns the layout of the panel.
+    pub fn layout(&self) -> &PanelLayout {
+        &self.layout
+    }
+
+    /// Returns the backend sending the SCK and SDA data.
+    pub fn shift_out(&mut self) -> &mut OUT {
+        &mut self.out
+    }
 
-            }
-            self.pulse_sck();
-            i-=1;
+    /// Send a full row of bytes in the column and channel order of the
+    /// layout and pulse LAT low. The gamma correction is applied to every
+    /// pixel before sending them. The previous row must be deactivated and
+    /// the new one activated. The pixels must already be oriented with
+    /// `PanelLayout::orient()`.
+    pub fn send_row(&mut self, row: usize, pixels: &[Color]) {
+        let mut corrected = [Color::default(); 8];
+        for (out, pixel) in corrected.iter_mut().zip(pixels) {
+            *out = self.gamma.correct(*pixel);
         }
+        self.latch_row(row, &corrected);
+    }
 
+    /// Send a full row of 12-bit pixels as `send_row()` does, with the 8-bit
+    /// output of `subframe` of the temporal dithering. Calling it for every
+    /// row and successive subframes renders the pixels with 12 bits of
+    /// precision.
+    pub fn send_hdr_row(&mut self, row: usize, pixels: &[HdrColor], subframe: usize) {
+        let dithered = hdr::dither_row(pixels, row, subframe, self.hdr_gamma);
+        self.latch_row(row, &dithered);
     }
 
-    /// Send a full row of bytes in BGR order and pulse LAT low. Gamma correction
-    /// must be applied to every pixel before sending them. The previous row must
-    /// be deactivated and the new one activated.
-    pub fn send_row(&mut self, row: usize, pixels: &[Color]) {
-        let mut prec_row;
-        match row {
-            n if n>0 => {prec_row = (row-1)%8;},
-            _ => {prec_row = 7;}
+    /// Start sending a row as `send_row()` does, leaving the previous row on
+    /// until `finish_row()` latches it. A row started before and not
+    /// finished yet is latched first.
+    pub fn start_row(&mut self, row: usize, pixels: &[Color]) {
+        let mut corrected = [Color::default(); 8];
+        for (out, pixel) in corrected.iter_mut().zip(pixels) {
+            *out = self.gamma.correct(*pixel);
         }
-        
-        self.row(prec_row, PinState::Low);
-        for i in (0..8).rev() {
-            let current: Color = pixels[i];
-            self.send_byte(gamma::gamma_correct(current.b));
-            self.send_byte(gamma::gamma_correct(current.g));
-            self.send_byte(gamma::gamma_correct(current.r));
+        self.start_latch(row, &corrected);
+    }
+
+    /// Start sending a row of 12-bit pixels as `send_hdr_row()` does, to be
+    /// latched by `finish_row()`.
+    pub fn start_hdr_row(&mut self, row: usize, pixels: &[HdrColor], subframe: usize) {
+        let dithered = hdr::dither_row(pixels, row, subframe, self.hdr_gamma);
+        self.start_latch(row, &dithered);
+    }
+
+    /// Wait for the end of the transfer of the row started by `start_row()`,
+    /// then switch the previous row off, pulse LAT low and switch the new
+    /// row on. Does nothing if no row was started.
+    pub fn finish_row(&mut self) {
+        if let Some(row) = self.pending.take() {
+            while !self.out.is_done() {}
+            self.row((row + 7) % 8, PinState::Low);
+            self.pulse_lat();
+            self.row(row, PinState::High);
         }
+    }
+
+    fn start_latch(&mut self, row: usize, pixels: &[Color; 8]) {
+        self.finish_row();
+        let data = self.layout.row_data(pixels);
+        self.out.start_shift_out(&data);
+        self.pending = Some(row);
+    }
+
+    /// Switch the previous row off, send already corrected pixels in the
+    /// order of the layout, pulse LAT low and switch the new row on.
+    fn latch_row(&mut self, row: usize, pixels: &[Color; 8]) {
+        self.finish_row();
+        let prec_row = match row {
+            n if n>0 => (row-1)%8,
+            _ => 7,
+        };
+
+        self.row(prec_row, PinState::Low);
+        let data = self.layout.row_data(pixels);
+        self.out.shift_out(&data);
         self.pulse_lat();
         self.row(row, PinState::High);
     }
 
-    /// Initialize bank0 by temporarily setting SB to low and sending 144 one bits,
-    /// pulsing SCK high after each bit and pulsing LAT low at the end. SB is then
+    /// Initialize bank0 by temporarily setting SB to low and sending the 144
+    /// bits of the dot correction, pulsing LAT low at the end. SB is then
     /// restored to high.
     fn init_bank0(&mut self) {
-        self.sb.set_low();
-        for _i in 0..144 {
-            self.sda.set_high();
-            self.pulse_sck();
-        }
+        // The shift register must not hold a row when SB goes low
+        self.finish_row();
+        self.sb.set_low().ok();
+        self.out.shift_out(&self.dot_correction.to_bank0(&self.layout));
         self.pulse_lat();
-        self.sb.set_high();
+        self.sb.set_high().ok();
+    }
+
+    /// Latch the calibration scaled by the brightness into bank0, if it
+    /// differs from the latched dot correction.
+    fn update_bank0(&mut self) {
+        let dot_correction = self.calibration.scaled(self.brightness);
+        if dot_correction != self.dot_correction {
+            self.dot_correction = dot_correction;
+            self.init_bank0();
+        }
     }
 
-    /// Display a full image, row by row, as fast as possible.
+    /// Display a full image in the orientation of the layout, row by row,
+    /// as fast as possible.
     pub fn display_image(&mut self, image: &Image) {
+        let image = self.layout.orient(image);
         for i in 0..8 {
             self.send_row(i, image.row(i));
         }
     }
 }
+
+#[cfg(feature = "hw-stm32l475")]
+mod stm32l475 {
+    use stm32l4xx_hal::delay::DelayCM;
+    use stm32l4xx_hal::pac::{DMA1, SPI1};
+    use stm32l4xx_hal::{gpio::*, rcc::Clocks};
+
+    use super::Matrix;
+    use crate::shift::{BitBang, Spi1Dma, DMA_BUFFER_LEN};
+
+    type Sb = PC5<Output<PushPull>>;
+    type Lat = PC4<Output<PushPull>>;
+    type Rst = PC3<Output<PushPull>>;
+    type Row = ErasedPin<Output<PushPull>>;
+
+    /// Matrix wired as on the STM32L475 IoT node. SCK and SDA are on PB1 and
+    /// PA4, which no SPI peripheral can drive, so they are bit-banged.
+    pub type Stm32l475Matrix = Matrix<Sb, Lat, Rst, BitBang<PB1<Output<PushPull>>, PA4<Output<PushPull>>>, Row>;
+
+    /// Matrix of the STM32L475 IoT node with SCK and SDA rewired from PB1 and
+    /// PA4 to PB3 and PB5, the SCK and MOSI pins of SPI1, which sends the
+    /// rows by DMA.
+    pub type Stm32l475SpiMatrix = Matrix<Sb, Lat, Rst, Spi1Dma, Row>;
+
+    /// Configure the row pins C0 to C7 as outputs, set low.
+    #[allow(clippy::too_many_arguments)]
+    fn rows(
+        pa2: PA2<Analog>,
+        pa3: PA3<Analog>,
+        pa5: PA5<Analog>,
+        pa6: PA6<Analog>,
+        pa7: PA7<Analog>,
+        pa15: PA15<Alternate<PushPull, 0>>,
+        pb0: PB0<Analog>,
+        pb2: PB2<Analog>,
+        gpioa_moder: &mut MODER<'A'>,
+        gpioa_otyper: &mut OTYPER<'A'>,
+        gpiob_moder: &mut MODER<'B'>,
+        gpiob_otyper: &mut OTYPER<'B'>,
+    ) -> [Row; 8] {
+        [
+            pb2.into_push_pull_output_in_state(gpiob_moder, gpiob_otyper, PinState::Low).set_speed(Speed::VeryHigh).erase(),
+            pa15.into_push_pull_output_in_state(gpioa_moder, gpioa_otyper, PinState::Low).set_speed(Speed::VeryHigh).erase(),
+            pa2.into_push_pull_output_in_state(gpioa_moder, gpioa_otyper, PinState::Low).set_speed(Speed::VeryHigh).erase(),
+            pa7.into_push_pull_output_in_state(gpioa_moder, gpioa_otyper, PinState::Low).set_speed(Speed::VeryHigh).erase(),
+            pa6.into_push_pull_output_in_state(gpioa_moder, gpioa_otyper, PinState::Low).set_speed(Speed::VeryHigh).erase(),
+            pa5.into_push_pull_output_in_state(gpioa_moder, gpioa_otyper, PinState::Low).set_speed(Speed::VeryHigh).erase(),
+            pb0.into_push_pull_output_in_state(gpiob_moder, gpiob_otyper, PinState::Low).set_speed(Speed::VeryHigh).erase(),
+            pa3.into_push_pull_output_in_state(gpioa_moder, gpioa_otyper, PinState::Low).set_speed(Speed::VeryHigh).erase(),
+        ]
+    }
+
+    /// Configure SB, LAT and RST as outputs, SB and LAT set high.
+    fn controls(
+        pc3: PC3<Analog>,
+        pc4: PC4<Analog>,
+        pc5: PC5<Analog>,
+        gpioc_moder: &mut MODER<'C'>,
+        gpioc_otyper: &mut OTYPER<'C'>,
+    ) -> (Sb, Lat, Rst) {
+        (
+            pc5.into_push_pull_output_in_state(gpioc_moder, gpioc_otyper, PinState::High).set_speed(Speed::VeryHigh),
+            pc4.into_push_pull_output_in_state(gpioc_moder, gpioc_otyper, PinState::High).set_speed(Speed::VeryHigh),
+            pc3.into_push_pull_output_in_state(gpioc_moder, gpioc_otyper, PinState::Low).set_speed(Speed::VeryHigh),
+        )
+    }
+
+    impl Stm32l475Matrix {
+        /// Create a new matrix from the control registers and the individual
+        /// unconfigured pins of the STM32L475 IoT node, then initialize it as
+        /// `from_pins()` does.
+        /// The pins will be set to very high speed mode.
+        #[allow(clippy::too_many_arguments)]   // Necessary to avoid a clippy warning
+        pub fn new(
+            pa2: PA2<Analog>,
+            pa3: PA3<Analog>,
+            pa4: PA4<Analog>,
+            pa5: PA5<Analog>,
+            pa6: PA6<Analog>,
+            pa7: PA7<Analog>,
+            pa15: PA15<Alternate<PushPull, 0>>,
+            pb0: PB0<Analog>,
+            pb1: PB1<Analog>,
+            pb2: PB2<Analog>,
+            pc3: PC3<Analog>,
+            pc4: PC4<Analog>,
+            pc5: PC5<Analog>,
+            gpioa_moder: &mut MODER<'A'>,
+            gpioa_otyper: &mut OTYPER<'A'>,
+            gpiob_moder: &mut MODER<'B'>,
+            gpiob_otyper: &mut OTYPER<'B'>,
+            gpioc_moder: &mut MODER<'C'>,
+            gpioc_otyper: &mut OTYPER<'C'>,
+            clocks: Clocks,
+        ) -> Self {
+            let rows = rows(pa2, pa3, pa5, pa6, pa7, pa15, pb0, pb2, gpioa_moder, gpioa_otyper, gpiob_moder, gpiob_otyper);
+            let (sb, lat, rst) = controls(pc3, pc4, pc5, gpioc_moder, gpioc_otyper);
+            Matrix::from_pins(
+                sb,
+                lat,
+                rst,
+                pb1.into_push_pull_output_in_state(gpiob_moder, gpiob_otyper, PinState::Low).set_speed(Speed::VeryHigh),
+                pa4.into_push_pull_output_in_state(gpioa_moder, gpioa_otyper, PinState::Low).set_speed(Speed::VeryHigh),
+                rows,
+                &mut DelayCM::new(clocks),
+            )
+        }
+    }
+
+    impl Stm32l475SpiMatrix {
+        /// Create a new matrix as `Stm32l475Matrix::new()` does, with SCK
+        /// and SDA on PB3 and PB5 driven by SPI1, which DMA1 feeds from
+        /// `buffer`. PB1 and PA4 are left unconfigured.
+        #[allow(clippy::too_many_arguments)]
+        pub fn new(
+            pa2: PA2<Analog>,
+            pa3: PA3<Analog>,
+            pa5: PA5<Analog>,
+            pa6: PA6<Analog>,
+            pa7: PA7<Analog>,
+            pa15: PA15<Alternate<PushPull, 0>>,
+            pb0: PB0<Analog>,
+            pb2: PB2<Analog>,
+            pb3: PB3<Alternate<PushPull, 0>>,
+            pb5: PB5<Analog>,
+            pc3: PC3<Analog>,
+            pc4: PC4<Analog>,
+            pc5: PC5<Analog>,
+            gpioa_moder: &mut MODER<'A'>,
+            gpioa_otyper: &mut OTYPER<'A'>,
+            gpiob_moder: &mut MODER<'B'>,
+            gpiob_otyper: &mut OTYPER<'B'>,
+            gpiob_afrl: &mut AFRL<'B'>,
+            gpioc_moder: &mut MODER<'C'>,
+            gpioc_otyper: &mut OTYPER<'C'>,
+            spi1: SPI1,
+            dma1: DMA1,
+            buffer: &'static mut [u8; DMA_BUFFER_LEN],
+            clocks: Clocks,
+        ) -> Self {
+            let rows = rows(pa2, pa3, pa5, pa6, pa7, pa15, pb0, pb2, gpioa_moder, gpioa_otyper, gpiob_moder, gpiob_otyper);
+            let (sb, lat, rst) = controls(pc3, pc4, pc5, gpioc_moder, gpioc_otyper);
+            // The pins stay in alternate function 5 once dropped
+            pb3.into_alternate::<5>(gpiob_moder, gpiob_otyper, gpiob_afrl).set_speed(Speed::VeryHigh);
+            pb5.into_alternate::<5>(gpiob_moder, gpiob_otyper, gpiob_afrl).set_speed(Speed::VeryHigh);
+            Matrix::with_shift_out(sb, lat, rst, Spi1Dma::new(spi1, dma1, buffer), rows, &mut DelayCM::new(clocks))
+        }
+    }
+}
+
+#[cfg(feature = "hw-stm32l475")]
+pub use stm32l475::{Stm32l475Matrix, Stm32l475SpiMatrix};
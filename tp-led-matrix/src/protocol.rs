@@ -0,0 +1,413 @@
+//! SE203 serial protocol: every image is sent as a 0xff sync byte followed by
+//! the 192 bytes of its pixels, row by row, in RGB order.
+//!
+//! Larger W×H images, for panels tiled from several matrices, are sent the
+//! same way with their W×H×3 pixel bytes. Both ends must agree on the size,
+//! which is a parameter of `Decoder`, `Packet` and `encode()`.
+//!
+//! With the legacy framing, pixel bytes are sent as is and 0xff cannot be
+//! represented. The stuffed framing (SE203 v2) reserves `SYNC`, `ESC` and
+//! `CMD`, and sends a pixel byte equal to one of them as `ESC` followed by
+//! the byte XOR 0x20: 0xff is sent as `0xfe 0xdf`, 0xfe as `0xfe 0xde` and
+//! 0xfd as `0xfe 0xdd`.
+//!
+//! `CMD` starts a command instead of a frame (see the `command` module).
+//! Commands only exist in the stuffed framing, where `CMD` cannot appear in
+//! a frame; with the legacy framing 0xfd is an ordinary pixel byte.
+//!
+//! A 12-bit `HdrImage` is sent in the stuffed framing as `CMD`, the
+//! `HDR_FRAME` opcode, then the `HdrImage::BYTES` bytes of
+//! `HdrImage::packed()`, stuffed like pixel bytes. It has the size of the
+//! 8-bit images, `hdr::HDR_FRAME_LEN` bytes for 8×8 ones.
+//!
+//! The stuffed framing can also append to every frame the CRC-16 of its
+//! pixel bytes, most significant byte first and stuffed like the pixels. The
+//! receiver then answers every frame, 12-bit ones included, with `ACK` or
+//! `NACK`.
+
+use crate::command::{self, Command, Parser};
+use crate::hdr::HdrImage;
+use crate::image::{Image, Image8x8};
+
+/// Byte starting every frame.
+pub const SYNC: u8 = 0xff;
+
+/// Byte announcing an escaped pixel byte in the stuffed framing.
+pub const ESC: u8 = 0xfe;
+
+/// Byte starting every command in the stuffed framing.
+pub const CMD: u8 = 0xfd;
+
+/// Opcode following `CMD` to start a 12-bit frame instead of a command.
+pub const HDR_FRAME: u8 = 0x0b;
+
+/// Value XORed with an escaped pixel byte.
+const ESC_XOR: u8 = 0x20;
+
+/// Reply to a frame received with a valid checksum.
+pub const ACK: u8 = 0x06;
+
+/// Reply to a frame lost or received with an invalid checksum.
+pub const NACK: u8 = 0x15;
+
+/// How pixel bytes are sent on the wire.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Framing {
+    /// SE203: pixel bytes are sent as is and must not be 0xff.
+    Legacy,
+    /// SE203 v2: pixel bytes equal to `SYNC`, `ESC` or `CMD` are escaped.
+    Stuffed,
+}
+
+/// Bytes which must be escaped in the stuffed framing.
+fn is_reserved(b: u8) -> bool {
+    b == SYNC || b == ESC || b == CMD
+}
+
+/// What a `Decoder` received.
+#[derive(Clone, Copy)]
+pub enum Packet<'a, const W: usize = 8, const H: usize = 8> {
+    Image(&'a Image<W, H>),
+    /// 12-bit frame.
+    Hdr(&'a HdrImage<W, H>),
+    Command(Command),
+}
+
+/// Integrity check appended to every frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Checksum {
+    None,
+    /// CRC-16/CCITT-FALSE, available with the stuffed framing only.
+    Crc16,
+}
+
+impl Checksum {
+    /// Number of bytes of the trailer, before stuffing.
+    pub const fn trailer_len(&self) -> usize {
+        match self {
+            Checksum::None => 0,
+            Checksum::Crc16 => 2,
+        }
+    }
+}
+
+/// Compute the CRC-16/CCITT-FALSE (polynomial 0x1021, initial value 0xffff)
+/// of `data`.
+pub fn crc16(data: &[u8]) -> u16 {
+    crc16_of(data.iter().copied())
+}
+
+/// CRC-16 of bytes which are not stored contiguously.
+fn crc16_of(data: impl Iterator<Item = u8>) -> u16 {
+    let mut crc: u16 = 0xffff;
+    for b in data {
+        crc ^= (b as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { crc << 1 ^ 0x1021 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+/// Number of pixel bytes in a frame of an 8×8 image.
+pub const FRAME_LEN: usize = Image8x8::BYTES;
+
+/// Number of pixel bytes in a frame of a W×H image.
+pub const fn frame_len(width: usize, height: usize) -> usize {
+    width * height * 3
+}
+
+/// Anomaly detected in the received byte stream.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// A data byte arrived after a complete frame instead of a sync byte.
+    /// Data bytes are then dropped until the next sync byte.
+    Overrun,
+    /// A sync byte arrived before the frame or command was complete. Holds
+    /// the number of bytes received, which are discarded.
+    ShortFrame(usize),
+    /// `ESC` was followed by a byte which is not an escaped reserved byte.
+    /// The frame is discarded.
+    InvalidEscape(u8),
+    /// The command could not be parsed. It is discarded.
+    Command(command::Error),
+    /// The checksum of the frame does not match its pixel bytes. The frame
+    /// is discarded.
+    ChecksumMismatch,
+}
+
+/// Counters kept by a `Decoder` since its creation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Complete frames received.
+    pub frames: u32,
+    /// Frames followed by data bytes instead of a sync byte.
+    pub overruns: u32,
+    /// Frames interrupted by a sync byte.
+    pub short_frames: u32,
+    /// Data bytes dropped because they did not follow a sync byte.
+    pub dropped: u32,
+    /// Frames discarded because of an invalid escape sequence.
+    pub invalid_escapes: u32,
+    /// Frames discarded because of a checksum mismatch.
+    pub checksum_errors: u32,
+    /// Complete commands received.
+    pub commands: u32,
+    /// Commands discarded because they could not be parsed.
+    pub invalid_commands: u32,
+}
+
+impl Stats {
+    /// Frames and commands lost or corrupted.
+    pub fn errors(&self) -> u32 {
+        self.overruns + self.short_frames + self.invalid_escapes + self.checksum_errors + self.invalid_commands
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Waiting for a sync byte, dropping data bytes.
+    Unsynced,
+    /// Receiving the pixel bytes of a frame, then its checksum.
+    Receiving,
+    /// Receiving the bytes of a command.
+    Command,
+    /// Receiving the packed bytes of a 12-bit frame, then its checksum.
+    Hdr,
+    /// A frame has just been completed.
+    Complete,
+}
+
+/// Incremental decoder of a SE203 byte stream of W×H images.
+pub struct Decoder<const W: usize = 8, const H: usize = 8> {
+    framing: Framing,
+    checksum: Checksum,
+    image: Image<W, H>,
+    hdr: HdrImage<W, H>,
+    trailer: [u8; 2],
+    next_pos: usize,
+    state: State,
+    /// The previous byte was `ESC`, in the stuffed framing.
+    escaped: bool,
+    parser: Parser,
+    stats: Stats,
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Decoder::new()
+    }
+}
+
+impl Decoder {
+    /// Create a decoder of the legacy framing waiting for a sync byte.
+    pub fn new() -> Self {
+        Decoder::with_framing(Framing::Legacy)
+    }
+
+    /// Create a decoder of the given framing waiting for a sync byte.
+    pub fn with_framing(framing: Framing) -> Self {
+        Decoder::with_checksum(framing, Checksum::None)
+    }
+
+    /// Create a decoder of the given framing and checksum waiting for a
+    /// sync byte.
+    pub fn with_checksum(framing: Framing, checksum: Checksum) -> Self {
+        Decoder::sized(framing, checksum)
+    }
+}
+
+impl<const W: usize, const H: usize> Decoder<W, H> {
+    /// Create a decoder of W×H images of the given framing and checksum
+    /// waiting for a sync byte, such as `Decoder::<16, 8>::sized()`.
+    pub fn sized(framing: Framing, checksum: Checksum) -> Self {
+        assert!(framing == Framing::Stuffed || checksum == Checksum::None,
+            "A checksum requires the stuffed framing");
+        Decoder {
+            framing,
+            checksum,
+            image: Image::default(),
+            hdr: HdrImage::default(),
+            trailer: [0; 2],
+            next_pos: 0,
+            state: State::Unsynced,
+            escaped: false,
+            parser: Parser::new(),
+            stats: Stats::default(),
+        }
+    }
+
+    /// Handle one received byte. Return the image or the command once its
+    /// last byte has been received, or the anomaly this byte revealed.
+    pub fn push(&mut self, b: u8) -> Result<Option<Packet<'_, W, H>>, Error> {
+        let command = self.framing == Framing::Stuffed && b == CMD;
+        if b == SYNC || command {
+            let received = self.next_pos;
+            // The opcode of a 12-bit frame has already been received
+            let interrupted = match self.state {
+                State::Receiving | State::Command => received > 0 || self.escaped,
+                State::Hdr => true,
+                _ => false,
+            };
+            self.next_pos = 0;
+            self.escaped = false;
+            self.state = if command { State::Command } else { State::Receiving };
+            self.parser = Parser::new();
+            if interrupted {
+                self.stats.short_frames += 1;
+                return Err(Error::ShortFrame(received));
+            }
+            return Ok(None);
+        }
+        match self.state {
+            State::Unsynced => {
+                self.stats.dropped += 1;
+                Ok(None)
+            }
+            State::Complete => {
+                self.stats.overruns += 1;
+                self.stats.dropped += 1;
+                self.state = State::Unsynced;
+                Err(Error::Overrun)
+            }
+            State::Receiving | State::Command | State::Hdr => match self.unstuff(b)? {
+                Some(HDR_FRAME) if self.state == State::Command && self.next_pos == 0 => {
+                    self.state = State::Hdr;
+                    Ok(None)
+                }
+                Some(b) if self.state == State::Command => self.push_command(b),
+                Some(b) => self.push_pixel(b),
+                None => Ok(None),
+            },
+        }
+    }
+
+    /// Return the byte `b` stands for, or `None` if it starts an escape
+    /// sequence.
+    fn unstuff(&mut self, b: u8) -> Result<Option<u8>, Error> {
+        if self.framing == Framing::Legacy {
+            return Ok(Some(b));
+        }
+        if !self.escaped {
+            self.escaped = b == ESC;
+            return Ok((!self.escaped).then_some(b));
+        }
+        self.escaped = false;
+        let unescaped = b ^ ESC_XOR;
+        if !is_reserved(unescaped) {
+            self.stats.invalid_escapes += 1;
+            self.next_pos = 0;
+            self.state = State::Unsynced;
+            return Err(Error::InvalidEscape(b));
+        }
+        Ok(Some(unescaped))
+    }
+
+    /// Handle a pixel byte of an 8-bit frame, or a packed byte of a 12-bit
+    /// one, or a byte of their checksum.
+    fn push_pixel(&mut self, b: u8) -> Result<Option<Packet<'_, W, H>>, Error> {
+        let hdr = self.state == State::Hdr;
+        let len = if hdr { HdrImage::<W, H>::BYTES } else { Image::<W, H>::BYTES };
+        if self.next_pos < len && hdr {
+            // 12-bit frames are unpacked as they are received
+            self.hdr.set_packed(self.next_pos, b);
+        } else if self.next_pos < len {
+            self.image.as_bytes_mut()[self.next_pos] = b;
+        } else {
+            self.trailer[self.next_pos - len] = b;
+        }
+        self.next_pos += 1;
+        if self.next_pos < len + self.checksum.trailer_len() {
+            return Ok(None);
+        }
+        self.next_pos = 0;
+        self.state = State::Complete;
+        let crc = match (self.checksum, hdr) {
+            (Checksum::None, _) => None,
+            (Checksum::Crc16, true) => Some(crc16_of(self.hdr.packed())),
+            (Checksum::Crc16, false) => Some(crc16(self.image.as_bytes())),
+        };
+        if crc.is_some_and(|crc| crc != u16::from_be_bytes(self.trailer)) {
+            self.stats.checksum_errors += 1;
+            return Err(Error::ChecksumMismatch);
+        }
+        self.stats.frames += 1;
+        if hdr {
+            return Ok(Some(Packet::Hdr(&self.hdr)));
+        }
+        Ok(Some(Packet::Image(&self.image)))
+    }
+
+    fn push_command(&mut self, b: u8) -> Result<Option<Packet<'_, W, H>>, Error> {
+        self.next_pos += 1;
+        match self.parser.push(b) {
+            Ok(None) => Ok(None),
+            Ok(Some(command)) => {
+                self.next_pos = 0;
+                self.state = State::Complete;
+                self.stats.commands += 1;
+                Ok(Some(Packet::Command(command)))
+            }
+            Err(e) => {
+                self.next_pos = 0;
+                self.state = State::Unsynced;
+                self.stats.invalid_commands += 1;
+                Err(Error::Command(e))
+            }
+        }
+    }
+
+    /// Return the counters kept since the creation of the decoder.
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+}
+
+/// Send `image` as one frame of the given framing, byte by byte, to `out`.
+/// With the legacy framing, 0xff pixel bytes are sent as 0xfe.
+pub fn encode<const W: usize, const H: usize>(image: &Image<W, H>, framing: Framing, out: impl FnMut(u8)) {
+    encode_with_checksum(image, framing, Checksum::None, out);
+}
+
+/// Send `image` as one frame of the given framing and checksum, byte by
+/// byte, to `out`.
+pub fn encode_with_checksum<const W: usize, const H: usize>(image: &Image<W, H>, framing: Framing, checksum: Checksum, mut out: impl FnMut(u8)) {
+    assert!(framing == Framing::Stuffed || checksum == Checksum::None,
+        "A checksum requires the stuffed framing");
+    let crc = crc16(image.as_bytes()).to_be_bytes();
+    out(SYNC);
+    for &b in image.as_bytes().iter().chain(&crc[..checksum.trailer_len()]) {
+        match framing {
+            Framing::Legacy => out(b.min(SYNC - 1)),
+            Framing::Stuffed => stuff(b, &mut out),
+        }
+    }
+}
+
+/// Send the 12-bit `image` as one frame of the stuffed framing with the
+/// given checksum, byte by byte, to `out`.
+pub fn encode_hdr<const W: usize, const H: usize>(image: &HdrImage<W, H>, checksum: Checksum, mut out: impl FnMut(u8)) {
+    let crc = crc16_of(image.packed()).to_be_bytes();
+    out(CMD);
+    stuff(HDR_FRAME, &mut out);
+    for b in image.packed().chain(crc[..checksum.trailer_len()].iter().copied()) {
+        stuff(b, &mut out);
+    }
+}
+
+/// Send `command` in the stuffed framing, byte by byte, to `out`.
+pub fn encode_command(command: &Command, mut out: impl FnMut(u8)) {
+    out(CMD);
+    command.write(|b| stuff(b, &mut out));
+}
+
+/// Send `b` to `out`, escaping it if it is reserved.
+fn stuff(b: u8, out: &mut impl FnMut(u8)) {
+    if is_reserved(b) {
+        out(ESC);
+        out(b ^ ESC_XOR);
+    } else {
+        out(b);
+    }
+}
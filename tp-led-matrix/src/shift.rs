@@ -0,0 +1,172 @@
+//! Backends shifting data into the DM163 through its SCK and SDA inputs.
+//!
+//! `BitBang` toggles two GPIO outputs and works with any pins, at the cost of
+//! a few CPU cycles per bit. `SpiOut` hands the bytes to a SPI peripheral
+//! configured in `MODE`, on boards where SCK and SDA are wired to its SCK and
+//! MOSI pins. On the STM32L475, `Spi1Dma` feeds SPI1 by DMA so that rows are
+//! shifted out in the background.
+
+use core::convert::Infallible;
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+
+/// SPI mode expected by the DM163: SCK idles low and SDA is sampled on its
+/// rising edge.
+pub const MODE: embedded_hal::spi::Mode = embedded_hal::spi::MODE_0;
+
+/// Sink of the serial data of the DM163.
+pub trait ShiftOut {
+    /// Shift `bytes` out, most significant bit first, with SDA stable on
+    /// every rising edge of SCK. SCK is low when this returns.
+    fn shift_out(&mut self, bytes: &[u8]);
+
+    /// Start shifting `bytes` out as `shift_out()` does, returning before
+    /// the end of the transfer if the backend can.
+    fn start_shift_out(&mut self, bytes: &[u8]) {
+        self.shift_out(bytes);
+    }
+
+    /// Returns whether the last transfer is over, SCK being low.
+    fn is_done(&mut self) -> bool {
+        true
+    }
+}
+
+/// Shift data out by toggling the SCK and SDA pins.
+pub struct BitBang<SCK, SDA> {
+    sck: SCK,
+    sda: SDA,
+}
+
+impl<SCK, SDA> BitBang<SCK, SDA>
+where
+    SCK: OutputPin<Error = Infallible>,
+    SDA: OutputPin<Error = Infallible>,
+{
+    /// Create a backend from already configured output pins, which are set
+    /// low.
+    pub fn new(mut sck: SCK, mut sda: SDA) -> Self {
+        sck.set_low().ok();
+        sda.set_low().ok();
+        BitBang { sck, sda }
+    }
+
+    /// Return the SCK and SDA pins.
+    pub fn free(self) -> (SCK, SDA) {
+        (self.sck, self.sda)
+    }
+}
+
+impl<SCK, SDA> ShiftOut for BitBang<SCK, SDA>
+where
+    SCK: OutputPin<Error = Infallible>,
+    SDA: OutputPin<Error = Infallible>,
+{
+    /// Set SDA to every bit in turn and pulse SCK high after each one.
+    fn shift_out(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            for i in (0..8).rev() {
+                match byte >> i & 1 {
+                    0 => self.sda.set_low().ok(),
+                    _ => self.sda.set_high().ok(),
+                };
+                self.sck.set_high().ok();
+                self.sck.set_low().ok();
+            }
+        }
+    }
+}
+
+/// Shift data out through a SPI peripheral, blocking until the last byte is
+/// sent. Nothing is received, so MISO needs not be connected.
+pub struct SpiOut<SPI>(SPI);
+
+impl<SPI: spi::Write<u8>> SpiOut<SPI> {
+    /// Create a backend from a SPI peripheral configured in `MODE`, MSB
+    /// first, with 8-bit frames.
+    pub fn new(spi: SPI) -> Self {
+        SpiOut(spi)
+    }
+
+    /// Return the SPI peripheral.
+    pub fn free(self) -> SPI {
+        self.0
+    }
+}
+
+impl<SPI: spi::Write<u8>> ShiftOut for SpiOut<SPI> {
+    fn shift_out(&mut self, bytes: &[u8]) {
+        self.0.write(bytes).ok();
+    }
+}
+
+#[cfg(feature = "hw-stm32l475")]
+mod stm32l475 {
+    use stm32l4xx_hal::pac::{DMA1, RCC, SPI1};
+
+    use super::ShiftOut;
+
+    /// Number of bytes of the largest transfer, a row of 8 pixels.
+    pub const DMA_BUFFER_LEN: usize = 24;
+
+    /// Shift data out through SPI1 at 20MHz, fed by the channel 3 of DMA1,
+    /// which raises the `DMA1_CH3` interrupt at the end of every transfer.
+    /// SCK and SDA must be wired to PB3 and PB5, in alternate function 5.
+    pub struct Spi1Dma {
+        spi: SPI1,
+        dma: DMA1,
+        buffer: &'static mut [u8; DMA_BUFFER_LEN],
+    }
+
+    impl Spi1Dma {
+        /// Enable SPI1 and DMA1, clocked at 80MHz, and configure SPI1 in
+        /// `MODE`, MSB first, with 8-bit frames sent from `buffer`.
+        pub fn new(spi: SPI1, dma: DMA1, buffer: &'static mut [u8; DMA_BUFFER_LEN]) -> Self {
+            // Only the enable bits of the peripherals owned here are changed
+            let rcc = unsafe { &*RCC::ptr() };
+            rcc.apb2enr.modify(|_, w| w.spi1en().set_bit());
+            rcc.ahb1enr.modify(|_, w| w.dma1en().set_bit());
+            spi.cr2.write(|w| unsafe { w.ds().bits(0b0111) }.frxth().set_bit().txdmaen().set_bit());
+            // Master with a software NSS, fPCLK/4, CPOL and CPHA cleared
+            spi.cr1.write(|w| unsafe { w.br().bits(0b001) }.mstr().set_bit().ssm().set_bit().ssi().set_bit().spe().set_bit());
+            // SPI1_TX is the request 1 of channel 3
+            dma.cselr.modify(|_, w| unsafe { w.c3s().bits(0b0001) });
+            dma.cpar3.write(|w| unsafe { w.pa().bits(&spi.dr as *const _ as u32) });
+            Spi1Dma { spi, dma, buffer }
+        }
+
+        /// Acknowledge the `DMA1_CH3` interrupt.
+        pub fn clear_interrupt(&mut self) {
+            self.dma.ifcr.write(|w| w.ctcif3().set_bit());
+        }
+    }
+
+    impl ShiftOut for Spi1Dma {
+        fn shift_out(&mut self, bytes: &[u8]) {
+            self.start_shift_out(bytes);
+            while !self.is_done() {}
+        }
+
+        /// Copy `bytes` into the DMA buffer, after the end of the previous
+        /// transfer, and start sending them.
+        fn start_shift_out(&mut self, bytes: &[u8]) {
+            assert!(bytes.len() <= DMA_BUFFER_LEN, "transfers are at most {} bytes", DMA_BUFFER_LEN);
+            while !self.is_done() {}
+            self.buffer[..bytes.len()].copy_from_slice(bytes);
+            self.dma.ccr3.modify(|_, w| w.en().clear_bit());
+            self.dma.cmar3.write(|w| unsafe { w.ma().bits(self.buffer.as_ptr() as u32) });
+            self.dma.cndtr3.write(|w| unsafe { w.ndt().bits(bytes.len() as u16) });
+            // Bytes from memory to the peripheral, with an interrupt at the end
+            self.dma.ccr3.write(|w| w.minc().set_bit().dir().set_bit().tcie().set_bit().en().set_bit());
+        }
+
+        /// The DMA has handed every byte to SPI1, which has sent them.
+        fn is_done(&mut self) -> bool {
+            let sr = self.spi.sr.read();
+            self.dma.cndtr3.read().ndt().bits() == 0 && sr.ftlvl().bits() == 0 && sr.bsy().bit_is_clear()
+        }
+    }
+}
+
+#[cfg(feature = "hw-stm32l475")]
+pub use stm32l475::{Spi1Dma, DMA_BUFFER_LEN};
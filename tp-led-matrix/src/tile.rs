@@ -0,0 +1,104 @@
+//! Panels made of several 8×8 matrices: a large `Image<W, H>` is drawn as a
+//! virtual canvas, then split into the images of every module.
+//!
+//! Modules are numbered in the order they are chained, which is also the
+//! order of the images returned by `Tiling::split()`. Every module still
+//! applies its own `PanelLayout` when it displays its image.
+
+use crate::image::{Image, Image8x8};
+
+/// Size of the side of a module, in pixels.
+pub const MODULE_SIZE: usize = 8;
+
+/// Position of a module on the virtual canvas, in modules from the top-left
+/// corner.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Tile {
+    /// Column of the module, its left pixel being at column 8×`x`.
+    pub x: usize,
+    /// Row of the module, its top pixel being at row 8×`y`.
+    pub y: usize,
+}
+
+/// Order in which the modules of a grid are chained.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ChainOrder {
+    /// Every row of modules from left to right.
+    RowMajor,
+    /// Even rows of modules from left to right, odd rows from right to left,
+    /// so that the chain winds through the grid.
+    Serpentine,
+}
+
+/// Placement of `N` chained modules on a virtual canvas.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Tiling<const N: usize> {
+    tiles: [Tile; N],
+}
+
+impl<const N: usize> Tiling<N> {
+    /// Place the modules at the given positions, in chain order.
+    pub const fn new(tiles: [Tile; N]) -> Self {
+        Tiling { tiles }
+    }
+
+    /// Place the modules on a grid `columns` modules wide, chained in
+    /// `order`. The last row is partial if `N` is not a multiple of
+    /// `columns`.
+    pub fn grid(columns: usize, order: ChainOrder) -> Self {
+        assert!(columns > 0, "A grid needs at least one column");
+        let mut tiles = [Tile::default(); N];
+        for (n, tile) in tiles.iter_mut().enumerate() {
+            let (y, x) = (n / columns, n % columns);
+            let x = match order {
+                ChainOrder::Serpentine if y % 2 == 1 => columns - 1 - x,
+                _ => x,
+            };
+            *tile = Tile { x, y };
+        }
+        Tiling { tiles }
+    }
+
+    /// Returns the positions of the modules, in chain order.
+    pub fn tiles(&self) -> &[Tile; N] {
+        &self.tiles
+    }
+
+    /// Returns the image of every module, in chain order. Pixels of a
+    /// module outside of `image` are black.
+    pub fn split<const W: usize, const H: usize>(&self, image: &Image<W, H>) -> [Image8x8; N] {
+        self.tiles.map(|tile| {
+            let mut module = Image8x8::default();
+            for row in 0..MODULE_SIZE {
+                for col in 0..MODULE_SIZE {
+                    let (y, x) = (MODULE_SIZE * tile.y + row, MODULE_SIZE * tile.x + col);
+                    if y < H && x < W {
+                        module[(row, col)] = image[(y, x)];
+                    }
+                }
+            }
+            module
+        })
+    }
+
+    /// Rebuild the virtual canvas from the images of the modules, in chain
+    /// order, as `split()` cut it. Pixels covered by no module are black,
+    /// and the last module wins where modules overlap.
+    pub fn join<const W: usize, const H: usize>(&self, modules: &[Image8x8; N]) -> Image<W, H> {
+        let mut image = Image::default();
+        for (tile, module) in self.tiles.iter().zip(modules) {
+            for row in 0..MODULE_SIZE {
+                for col in 0..MODULE_SIZE {
+                    let (y, x) = (MODULE_SIZE * tile.y + row, MODULE_SIZE * tile.x + col);
+                    if y < H && x < W {
+                        image[(y, x)] = module[(row, col)];
+                    }
+                }
+            }
+        }
+        image
+    }
+}
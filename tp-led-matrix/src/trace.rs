@@ -0,0 +1,327 @@
+//! Host-side recording of the DM163 pin activity.
+//!
+//! A `Recorder` hands out `RecordingPin`s that log every write with a logical
+//! timestamp: each pin write takes one tick, and a delay of one millisecond
+//! takes 1000 ticks. The log can be dumped as a VCD file for GTKWave, or
+//! decoded back into the data latched into the DM163 banks. A `RecordingSpi`
+//! records the SCK and SDA levels a SPI peripheral would output, so that
+//! both shift-out backends give comparable logs.
+
+use std::cell::RefCell;
+use std::convert::Infallible;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::blocking::spi;
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::matrix::Matrix;
+use crate::shift::{BitBang, SpiOut};
+
+/// A signal between the microcontroller and the LED matrix.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Signal {
+    Sb,
+    Lat,
+    Rst,
+    Sck,
+    Sda,
+    /// One of the row outputs C0 to C7.
+    Row(u8),
+}
+
+impl Signal {
+    /// Every signal, in the order used by the VCD dump.
+    pub const ALL: [Signal; 13] = [
+        Signal::Sb,
+        Signal::Lat,
+        Signal::Rst,
+        Signal::Sck,
+        Signal::Sda,
+        Signal::Row(0),
+        Signal::Row(1),
+        Signal::Row(2),
+        Signal::Row(3),
+        Signal::Row(4),
+        Signal::Row(5),
+        Signal::Row(6),
+        Signal::Row(7),
+    ];
+
+    /// Name of the signal in the board schematics.
+    pub fn name(&self) -> String {
+        match self {
+            Signal::Sb => "SB".into(),
+            Signal::Lat => "LAT".into(),
+            Signal::Rst => "RST".into(),
+            Signal::Sck => "SCK".into(),
+            Signal::Sda => "SDA".into(),
+            Signal::Row(n) => format!("C{}", n),
+        }
+    }
+
+    fn index(&self) -> usize {
+        Signal::ALL.iter().position(|s| s == self).unwrap()
+    }
+}
+
+/// One pin write.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Event {
+    pub time: u64,
+    pub signal: Signal,
+    pub level: bool,
+}
+
+#[derive(Default)]
+struct Log {
+    time: u64,
+    /// Levels of the signals before the first recorded event.
+    initial: [Option<bool>; 13],
+    /// Levels of the signals after the last recorded event.
+    current: [Option<bool>; 13],
+    events: Vec<Event>,
+}
+
+/// Shared log of pin writes. Clones refer to the same log.
+#[derive(Clone, Default)]
+pub struct Recorder(Rc<RefCell<Log>>);
+
+/// Matrix driven through recording pins.
+pub type RecordingMatrix =
+    Matrix<RecordingPin, RecordingPin, RecordingPin, BitBang<RecordingPin, RecordingPin>, RecordingPin>;
+
+/// Matrix driven through recording pins and a recording SPI peripheral.
+pub type RecordingSpiMatrix = Matrix<RecordingPin, RecordingPin, RecordingPin, SpiOut<RecordingSpi>, RecordingPin>;
+
+impl Recorder {
+    /// Create a recorder with an empty log.
+    pub fn new() -> Self {
+        Recorder::default()
+    }
+
+    /// Return a pin which records its writes as `signal`.
+    pub fn pin(&self, signal: Signal) -> RecordingPin {
+        RecordingPin { signal, recorder: self.clone() }
+    }
+
+    /// Return a delay which advances the logical clock.
+    pub fn delay(&self) -> RecordingDelay {
+        RecordingDelay { recorder: self.clone() }
+    }
+
+    /// Build a matrix whose pins all record into this log.
+    pub fn matrix(&self) -> RecordingMatrix {
+        Matrix::from_pins(
+            self.pin(Signal::Sb),
+            self.pin(Signal::Lat),
+            self.pin(Signal::Rst),
+            self.pin(Signal::Sck),
+            self.pin(Signal::Sda),
+            [0, 1, 2, 3, 4, 5, 6, 7].map(|n| self.pin(Signal::Row(n))),
+            &mut self.delay(),
+        )
+    }
+
+    /// Return a SPI peripheral which records its output on SCK and SDA.
+    /// SCK is set to its idle low level, as when the peripheral is enabled.
+    pub fn spi(&self) -> RecordingSpi {
+        self.record(Signal::Sck, false);
+        RecordingSpi { recorder: self.clone() }
+    }
+
+    /// Build a matrix whose pins and SPI peripheral all record into this log.
+    pub fn spi_matrix(&self) -> RecordingSpiMatrix {
+        Matrix::with_shift_out(
+            self.pin(Signal::Sb),
+            self.pin(Signal::Lat),
+            self.pin(Signal::Rst),
+            SpiOut::new(self.spi()),
+            [0, 1, 2, 3, 4, 5, 6, 7].map(|n| self.pin(Signal::Row(n))),
+            &mut self.delay(),
+        )
+    }
+
+    /// Return a copy of the events recorded so far.
+    pub fn events(&self) -> Vec<Event> {
+        self.0.borrow().events.clone()
+    }
+
+    /// Forget the events recorded so far. The clock keeps running and the
+    /// current levels become the initial levels of the log.
+    pub fn clear(&self) {
+        let mut log = self.0.borrow_mut();
+        log.initial = log.current;
+        log.events.clear();
+    }
+
+    fn record(&self, signal: Signal, level: bool) {
+        let mut log = self.0.borrow_mut();
+        let time = log.time;
+        log.events.push(Event { time, signal, level });
+        log.current[signal.index()] = Some(level);
+        log.time += 1;
+    }
+
+    /// Dump the recorded events as a Value Change Dump. Writes which do not
+    /// change the level of a signal are omitted, and signals which had not
+    /// been written before the log started are undefined (`x`).
+    pub fn write_vcd<W: Write>(&self, mut out: W) -> io::Result<()> {
+        let log = self.0.borrow();
+        writeln!(out, "$version tp-led-matrix trace $end")?;
+        writeln!(out, "$timescale 1us $end")?;
+        writeln!(out, "$scope module dm163 $end")?;
+        for signal in Signal::ALL {
+            writeln!(out, "$var wire 1 {} {} $end", vcd_id(signal), signal.name())?;
+        }
+        writeln!(out, "$upscope $end")?;
+        writeln!(out, "$enddefinitions $end")?;
+        writeln!(out, "$dumpvars")?;
+        for (signal, level) in Signal::ALL.iter().zip(log.initial) {
+            let value = level.map_or('x', |level| if level { '1' } else { '0' });
+            writeln!(out, "{}{}", value, vcd_id(*signal))?;
+        }
+        writeln!(out, "$end")?;
+
+        let mut levels = log.initial;
+        let mut last_time = None;
+        for event in log.events.iter() {
+            let level = &mut levels[event.signal.index()];
+            if *level == Some(event.level) {
+                continue;
+            }
+            *level = Some(event.level);
+            if last_time != Some(event.time) {
+                writeln!(out, "#{}", event.time)?;
+                last_time = Some(event.time);
+            }
+            writeln!(out, "{}{}", event.level as u8, vcd_id(event.signal))?;
+        }
+        Ok(())
+    }
+
+    /// Reconstruct the data latched into the DM163 from the recorded events.
+    pub fn decode(&self) -> Vec<Transfer> {
+        let log = self.0.borrow();
+        decode(log.initial, &log.events)
+    }
+}
+
+fn vcd_id(signal: Signal) -> char {
+    (b'!' + signal.index() as u8) as char
+}
+
+/// Output pin recording its writes into a `Recorder`.
+pub struct RecordingPin {
+    signal: Signal,
+    recorder: Recorder,
+}
+
+impl OutputPin for RecordingPin {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        self.recorder.record(self.signal, false);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        self.recorder.record(self.signal, true);
+        Ok(())
+    }
+}
+
+/// SPI peripheral in `shift::MODE` recording the SCK and SDA levels of every
+/// bit it sends into a `Recorder`.
+pub struct RecordingSpi {
+    recorder: Recorder,
+}
+
+impl spi::Write<u8> for RecordingSpi {
+    type Error = Infallible;
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Infallible> {
+        for &word in words {
+            for i in (0..8).rev() {
+                self.recorder.record(Signal::Sda, word >> i & 1 != 0);
+                self.recorder.record(Signal::Sck, true);
+                self.recorder.record(Signal::Sck, false);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Delay advancing the clock of a `Recorder` instead of waiting.
+pub struct RecordingDelay {
+    recorder: Recorder,
+}
+
+impl DelayMs<u8> for RecordingDelay {
+    fn delay_ms(&mut self, ms: u8) {
+        self.recorder.0.borrow_mut().time += 1000 * ms as u64;
+    }
+}
+
+/// Data latched into the DM163 by a low pulse on LAT.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Transfer {
+    /// 144 bits latched into bank 0 while SB was low, as 24 six-bit
+    /// dot-correction values in shift order.
+    Bank0([u8; 24]),
+    /// 192 bits latched into bank 1 while SB was high, as 24 bytes in shift
+    /// order, and the row switched on after the latch, if any.
+    Row { row: Option<usize>, data: [u8; 24] },
+    /// A latch after an unexpected number of bits.
+    Malformed { sb: bool, bits: Vec<bool> },
+}
+
+/// Sample SDA on every rising edge of SCK and split the bits on every falling
+/// edge of LAT, the bank being selected by the level of SB at that time.
+/// `initial` holds the levels of the signals, in `Signal::ALL` order, before
+/// the first event.
+pub fn decode(initial: [Option<bool>; 13], events: &[Event]) -> Vec<Transfer> {
+    let mut levels = initial;
+    let mut bits = Vec::new();
+    let mut transfers = Vec::new();
+    let mut pending_row = None;
+    for event in events {
+        let index = event.signal.index();
+        let previous = levels[index];
+        levels[index] = Some(event.level);
+        let rising = previous == Some(false) && event.level;
+        let falling = previous == Some(true) && !event.level;
+        match event.signal {
+            Signal::Sck if rising => bits.push(levels[Signal::Sda.index()] == Some(true)),
+            Signal::Lat if falling => {
+                let sb = levels[Signal::Sb.index()] == Some(true);
+                let transfer = match (sb, bits.len()) {
+                    (false, 144) => Transfer::Bank0(pack(&bits, 6)),
+                    (true, 192) => Transfer::Row { row: None, data: pack(&bits, 8) },
+                    _ => Transfer::Malformed { sb, bits: bits.clone() },
+                };
+                pending_row = matches!(transfer, Transfer::Row { .. }).then(|| transfers.len());
+                transfers.push(transfer);
+                bits.clear();
+            }
+            Signal::Row(n) if rising => {
+                if let Some(Transfer::Row { row, .. }) = pending_row.map(|i| &mut transfers[i]) {
+                    *row = Some(n as usize);
+                }
+                pending_row = None;
+            }
+            _ => {}
+        }
+    }
+    transfers
+}
+
+/// Pack bits, MSB first, into 24 values of `width` bits.
+fn pack(bits: &[bool], width: usize) -> [u8; 24] {
+    let mut values = [0; 24];
+    for (value, chunk) in values.iter_mut().zip(bits.chunks(width)) {
+        *value = chunk.iter().fold(0, |acc, &bit| acc << 1 | bit as u8);
+    }
+    values
+}
@@ -0,0 +1,142 @@
+//! Geometric transforms of images, for panels mounted in other orientations
+//! and for sliding effects. Every transform returns a new image, without
+//! allocating.
+
+use crate::hdr::HdrImage;
+use crate::{Color, Image};
+
+/// What enters an image shifted by `Image::shift()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Edge {
+    /// The pixels leaving on one side enter on the opposite side.
+    Wrap,
+    /// The entering pixels have the given color.
+    Fill(Color),
+}
+
+/// Build a `W2`×`H2` grid whose pixel at `(row, col)` is the pixel of
+/// `pixels` at `source(row, col)`, whatever the type of the pixels.
+fn remap<P: Copy, const W: usize, const H: usize, const W2: usize, const H2: usize>(
+    pixels: &[[P; W]; H],
+    source: impl Fn(usize, usize) -> (usize, usize),
+) -> [[P; W2]; H2] {
+    core::array::from_fn(|row| {
+        core::array::from_fn(|col| {
+            let (row, col) = source(row, col);
+            pixels[row][col]
+        })
+    })
+}
+
+// Sources of the pixels of every transform of a W×H image, shared by the
+// images of every pixel type
+
+const fn rotate90_source<const W: usize, const H: usize>(row: usize, col: usize) -> (usize, usize) {
+    (H - 1 - col, row)
+}
+
+const fn rotate180_source<const W: usize, const H: usize>(row: usize, col: usize) -> (usize, usize) {
+    (H - 1 - row, W - 1 - col)
+}
+
+const fn rotate270_source<const W: usize, const H: usize>(row: usize, col: usize) -> (usize, usize) {
+    (col, W - 1 - row)
+}
+
+const fn flip_horizontal_source<const W: usize, const H: usize>(row: usize, col: usize) -> (usize, usize) {
+    (row, W - 1 - col)
+}
+
+const fn flip_vertical_source<const W: usize, const H: usize>(row: usize, col: usize) -> (usize, usize) {
+    (H - 1 - row, col)
+}
+
+const fn transpose_source(row: usize, col: usize) -> (usize, usize) {
+    (col, row)
+}
+
+impl<const W: usize, const H: usize> Image<W, H> {
+    /// Rotates the image by 90° clockwise.
+    pub fn rotate90(&self) -> Image<H, W> {
+        Image(remap(&self.0, rotate90_source::<W, H>))
+    }
+
+    /// Rotates the image by 180°.
+    pub fn rotate180(&self) -> Image<W, H> {
+        Image(remap(&self.0, rotate180_source::<W, H>))
+    }
+
+    /// Rotates the image by 270° clockwise, or 90° counterclockwise.
+    pub fn rotate270(&self) -> Image<H, W> {
+        Image(remap(&self.0, rotate270_source::<W, H>))
+    }
+
+    /// Mirrors the image left to right.
+    pub fn flip_horizontal(&self) -> Image<W, H> {
+        Image(remap(&self.0, flip_horizontal_source::<W, H>))
+    }
+
+    /// Mirrors the image top to bottom.
+    pub fn flip_vertical(&self) -> Image<W, H> {
+        Image(remap(&self.0, flip_vertical_source::<W, H>))
+    }
+
+    /// Swaps rows and columns, mirroring the image along its main diagonal.
+    pub fn transpose(&self) -> Image<H, W> {
+        Image(remap(&self.0, transpose_source))
+    }
+
+    /// Moves the content of the image by `dx` columns to the right and `dy`
+    /// rows down, negative values moving it left and up. The uncovered
+    /// pixels are filled as told by `edge`.
+    pub fn shift(&self, dx: i32, dy: i32, edge: Edge) -> Image<W, H> {
+        // Computed on 64 bits, as `i32::MIN` cannot be negated
+        let (width, height) = (W as i64, H as i64);
+        let mut image = *self;
+        for row in 0..H {
+            for col in 0..W {
+                let (src_row, src_col) = (row as i64 - i64::from(dy), col as i64 - i64::from(dx));
+                image[(row, col)] = match edge {
+                    Edge::Wrap => self[(src_row.rem_euclid(height) as usize, src_col.rem_euclid(width) as usize)],
+                    Edge::Fill(color) if !(0..height).contains(&src_row) || !(0..width).contains(&src_col) => color,
+                    Edge::Fill(_) => self[(src_row as usize, src_col as usize)],
+                };
+            }
+        }
+        image
+    }
+}
+
+/// The same transforms as `Image`, for 12-bit images.
+impl<const W: usize, const H: usize> HdrImage<W, H> {
+    /// Rotates the image by 90° clockwise.
+    pub fn rotate90(&self) -> HdrImage<H, W> {
+        HdrImage(remap(&self.0, rotate90_source::<W, H>))
+    }
+
+    /// Rotates the image by 180°.
+    pub fn rotate180(&self) -> HdrImage<W, H> {
+        HdrImage(remap(&self.0, rotate180_source::<W, H>))
+    }
+
+    /// Rotates the image by 270° clockwise, or 90° counterclockwise.
+    pub fn rotate270(&self) -> HdrImage<H, W> {
+        HdrImage(remap(&self.0, rotate270_source::<W, H>))
+    }
+
+    /// Mirrors the image left to right.
+    pub fn flip_horizontal(&self) -> HdrImage<W, H> {
+        HdrImage(remap(&self.0, flip_horizontal_source::<W, H>))
+    }
+
+    /// Mirrors the image top to bottom.
+    pub fn flip_vertical(&self) -> HdrImage<W, H> {
+        HdrImage(remap(&self.0, flip_vertical_source::<W, H>))
+    }
+
+    /// Swaps rows and columns, mirroring the image along its main diagonal.
+    pub fn transpose(&self) -> HdrImage<H, W> {
+        HdrImage(remap(&self.0, transpose_source))
+    }
+}
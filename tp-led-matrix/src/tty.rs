@@ -0,0 +1,102 @@
+//! Terminal helpers for the host tools.
+
+use std::ffi::CStr;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::{AsRawFd, FromRawFd};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Pseudo-terminal pair. Bytes written to the slave, for example by
+/// `cat many_frames.bin > /dev/pts/N`, can be read from the master.
+pub struct Pty {
+    pub master: File,
+    /// Kept open so that reading the master does not fail between writers.
+    _slave: File,
+    pub path: PathBuf,
+}
+
+impl Pty {
+    /// Allocate a new pseudo-terminal in raw mode.
+    pub fn open() -> io::Result<Pty> {
+        let master = unsafe {
+            let fd = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY);
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            File::from_raw_fd(fd)
+        };
+        let mut name = [0 as libc::c_char; 64];
+        unsafe {
+            let fd = master.as_raw_fd();
+            if libc::grantpt(fd) < 0
+                || libc::unlockpt(fd) < 0
+                || libc::ptsname_r(fd, name.as_mut_ptr(), name.len()) != 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        let path = PathBuf::from(unsafe { CStr::from_ptr(name.as_ptr()) }.to_string_lossy().into_owned());
+        make_raw(&master)?;
+        let slave = OpenOptions::new().read(true).write(true).custom_flags(libc::O_NOCTTY).open(&path)?;
+        Ok(Pty { master, _slave: slave, path })
+    }
+}
+
+impl Read for Pty {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.master.read(buf)
+    }
+}
+
+/// Put a terminal in raw mode, so that every byte goes through unchanged.
+pub fn make_raw(file: &File) -> io::Result<()> {
+    update_termios(file, |termios| unsafe { libc::cfmakeraw(termios) })
+}
+
+/// Open a serial device and configure it like the board's USART1: 38400
+/// bauds, 8 data bits, no parity, 1 stop bit, no flow control, raw mode.
+/// This replaces `stty.sh`.
+pub fn open_serial(path: &Path) -> io::Result<File> {
+    let file = OpenOptions::new().read(true).write(true).custom_flags(libc::O_NOCTTY).open(path)?;
+    update_termios(&file, |termios| unsafe {
+        libc::cfmakeraw(termios);
+        termios.c_cflag &= !(libc::CSIZE | libc::PARENB | libc::CSTOPB | libc::CRTSCTS);
+        termios.c_cflag |= libc::CS8 | libc::CLOCAL | libc::CREAD;
+        libc::cfsetispeed(termios, libc::B38400);
+        libc::cfsetospeed(termios, libc::B38400);
+    })?;
+    Ok(file)
+}
+
+/// Read one byte, or return `None` if none arrives within `timeout`.
+pub fn read_byte_timeout(file: &mut File, timeout: Duration) -> io::Result<Option<u8>> {
+    let mut pollfd = libc::pollfd { fd: file.as_raw_fd(), events: libc::POLLIN, revents: 0 };
+    let ms = timeout.as_millis().min(libc::c_int::MAX as u128) as libc::c_int;
+    match unsafe { libc::poll(&mut pollfd, 1, ms) } {
+        n if n < 0 => return Err(io::Error::last_os_error()),
+        0 => return Ok(None),
+        _ => {}
+    }
+    let mut b = [0];
+    match file.read(&mut b)? {
+        0 => Ok(None),
+        _ => Ok(Some(b[0])),
+    }
+}
+
+/// Apply `change` to the attributes of a terminal.
+fn update_termios(file: &File, change: impl FnOnce(&mut libc::termios)) -> io::Result<()> {
+    unsafe {
+        let mut termios = std::mem::zeroed::<libc::termios>();
+        if libc::tcgetattr(file.as_raw_fd(), &mut termios) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        change(&mut termios);
+        if libc::tcsetattr(file.as_raw_fd(), libc::TCSANOW, &termios) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
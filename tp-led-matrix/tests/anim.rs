@@ -0,0 +1,103 @@
+//! Round trips of animation files and SE203 streams.
+
+use tp_led_matrix::anim::{Animation, Error, Frame, PixelFormat, MAGIC, VERSION};
+use tp_led_matrix::image::RED;
+use tp_led_matrix::protocol::{Checksum, Framing};
+use tp_led_matrix::{Color, Image};
+
+fn sample() -> Animation {
+    Animation {
+        width: 2,
+        height: 1,
+        format: PixelFormat::Rgb888,
+        loop_count: 3,
+        name: Some("blink".into()),
+        frames: vec![
+            Frame { duration_ms: 500, pixels: vec![0xff, 0, 0, 0, 0, 0xff] },
+            Frame { duration_ms: 250, pixels: vec![0; 6] },
+        ],
+    }
+}
+
+fn write(animation: &Animation) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    animation.write(&mut bytes).unwrap();
+    bytes
+}
+
+#[test]
+fn header_layout() {
+    let bytes = write(&sample());
+    assert_eq!(bytes[..4], MAGIC);
+    assert_eq!(bytes[4..17], [VERSION, 0, 0, 2, 0, 1, 0, 0, 0, 2, 0, 3, 5]);
+    assert_eq!(&bytes[17..22], b"blink");
+    assert_eq!(bytes[22..24], 500u16.to_be_bytes());
+    assert_eq!(bytes.len(), 22 + 2 * (2 + 6));
+}
+
+#[test]
+fn round_trip() {
+    let animation = sample();
+    assert_eq!(Animation::read(&write(&animation)[..]).unwrap(), animation);
+    let unnamed = Animation { name: None, ..sample() };
+    assert_eq!(Animation::read(&write(&unnamed)[..]).unwrap(), unnamed);
+}
+
+#[test]
+fn invalid_files() {
+    let bytes = write(&sample());
+    let mut magic = bytes.clone();
+    magic[0] = b'X';
+    assert!(matches!(Animation::read(&magic[..]), Err(Error::BadMagic)));
+    let mut version = bytes.clone();
+    version[4] = VERSION + 1;
+    assert!(matches!(Animation::read(&version[..]), Err(Error::UnsupportedVersion(2))));
+    let mut format = bytes.clone();
+    format[5] = 7;
+    assert!(matches!(Animation::read(&format[..]), Err(Error::UnknownPixelFormat(7))));
+    assert!(matches!(Animation::read(&bytes[..bytes.len() - 1]), Err(Error::Io(_))));
+}
+
+#[test]
+fn truncated_huge_frame_fails_without_allocating() {
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend([VERSION, PixelFormat::Rgb888.id()]);
+    bytes.extend(u16::MAX.to_be_bytes());
+    bytes.extend(u16::MAX.to_be_bytes());
+    bytes.extend(u32::MAX.to_be_bytes());
+    bytes.extend([0, 0, 0]);
+    bytes.extend([0, 100, 1, 2, 3]);
+    match Animation::read(&bytes[..]) {
+        Err(Error::Io(e)) => assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof),
+        other => panic!("unexpected {:?}", other.map(|animation| animation.frames.len())),
+    }
+}
+
+#[test]
+fn invalid_animations() {
+    let mut animation = sample();
+    animation.frames[1].pixels.pop();
+    assert!(matches!(animation.write(Vec::new()), Err(Error::FrameSize { index: 1, len: 5 })));
+    let long = Animation { name: Some("x".repeat(256)), ..sample() };
+    assert!(matches!(long.write(Vec::new()), Err(Error::InvalidName)));
+    assert!(matches!(sample().to_se203(Framing::Legacy, Checksum::None), Err(Error::NotSe203)));
+}
+
+#[test]
+fn se203_round_trip() {
+    let stream = include_bytes!("../one_frame.bin");
+    let animation = Animation::from_se203(stream, Framing::Legacy, Checksum::None, 40);
+    assert_eq!(animation.frames.len(), 1);
+    assert_eq!(animation.frames[0].duration_ms, 40);
+    let animation = Animation::read(&write(&animation)[..]).unwrap();
+    assert_eq!(animation.to_se203(Framing::Legacy, Checksum::None).unwrap(), stream);
+}
+
+#[test]
+fn se203_framing_conversion() {
+    let images = [Image::new_solid(RED), Image::gradient(Color { r: 0xff, g: 0xfe, b: 0xfd })];
+    let animation = Animation::from_images(&images, 100);
+    let stream = animation.to_se203(Framing::Stuffed, Checksum::Crc16).unwrap();
+    let decoded = Animation::from_se203(&stream, Framing::Stuffed, Checksum::Crc16, 100);
+    assert_eq!(decoded, animation);
+}
@@ -0,0 +1,94 @@
+//! Host tests of the color arithmetic and blending.
+
+use tp_led_matrix::blend::{BlendMode, RgbaColor};
+use tp_led_matrix::image::{self, BLACK, BLUE, RED, WHITE};
+use tp_led_matrix::{Color, Image, Image8x8};
+
+const MODES: [BlendMode; 4] = [BlendMode::Normal, BlendMode::Additive, BlendMode::Multiply, BlendMode::Screen];
+
+#[test]
+fn arithmetic_saturates() {
+    let a = Color { r: 200, g: 100, b: 0 };
+    let b = Color { r: 100, g: 50, b: 10 };
+    assert_eq!(a + b, Color { r: 255, g: 150, b: 10 });
+    assert_eq!(b - a, Color { r: 0, g: 0, b: 10 });
+    assert_eq!(a - b, Color { r: 100, g: 50, b: 0 });
+}
+
+#[test]
+fn channel_multiplication() {
+    let a = Color { r: 128, g: 255, b: 3 };
+    assert_eq!(a * WHITE, a);
+    assert_eq!(a * BLACK, BLACK);
+    assert_eq!(a * Color { r: 128, g: 64, b: 255 }, Color { r: 64, g: 64, b: 3 });
+}
+
+#[test]
+fn lerp_endpoints_and_middle() {
+    assert_eq!(Color::lerp(RED, BLUE, 0.0), RED);
+    assert_eq!(Color::lerp(RED, BLUE, 1.0), BLUE);
+    assert_eq!(Color::lerp(RED, BLUE, 0.5), Color { r: 128, g: 0, b: 128 });
+    assert_eq!(Color::lerp(RED, BLUE, -1.0), RED);
+    assert_eq!(Color::lerp(RED, BLUE, 3.0), BLUE);
+}
+
+#[test]
+fn blend_modes() {
+    let bottom = Color { r: 100, g: 200, b: 0 };
+    let top = Color { r: 200, g: 100, b: 50 };
+    assert_eq!(BlendMode::Normal.apply(bottom, top), top);
+    assert_eq!(BlendMode::Additive.apply(bottom, top), Color { r: 255, g: 255, b: 50 });
+    assert_eq!(BlendMode::Multiply.apply(bottom, top), Color { r: 78, g: 78, b: 0 });
+    assert_eq!(BlendMode::Screen.apply(bottom, top), Color { r: 222, g: 222, b: 50 });
+}
+
+#[test]
+fn neutral_colors() {
+    let color = Color { r: 12, g: 34, b: 56 };
+    assert_eq!(BlendMode::Additive.apply(color, BLACK), color);
+    assert_eq!(BlendMode::Multiply.apply(color, WHITE), color);
+    assert_eq!(BlendMode::Screen.apply(color, BLACK), color);
+    for mode in MODES {
+        assert_eq!(color.blend(image::ORANGE, mode, 0.0), color);
+    }
+}
+
+#[test]
+fn opacity_mixes_with_the_bottom() {
+    assert_eq!(BLACK.blend(WHITE, BlendMode::Normal, 0.25), Color { r: 64, g: 64, b: 64 });
+    assert_eq!(RED.blend(BLUE, BlendMode::Additive, 0.5), Color { r: 255, g: 0, b: 128 });
+}
+
+#[test]
+fn alpha_over_opaque() {
+    assert_eq!(RgbaColor::new(RED, 255).over_color(BLUE), RED);
+    assert_eq!(RgbaColor::new(RED, 0).over_color(BLUE), BLUE);
+    assert_eq!(RgbaColor::new(WHITE, 128).over_color(BLACK), Color { r: 128, g: 128, b: 128 });
+    assert_eq!(RgbaColor::from(RED).color(), RED);
+}
+
+#[test]
+fn alpha_over_translucent() {
+    let top = RgbaColor::new(RED, 128);
+    assert_eq!(top.over(RgbaColor::from(BLUE)), RgbaColor::from(top.over_color(BLUE)));
+    assert_eq!(top.over(RgbaColor::default()), top);
+    assert_eq!(RgbaColor::default().over(RgbaColor::default()), RgbaColor::default());
+    let half = RgbaColor::new(BLUE, 128).over(top);
+    assert_eq!(half.a, 192);
+    assert_eq!(half.color(), Color { r: 85, g: 0, b: 170 });
+}
+
+#[test]
+fn image_blending() {
+    let bottom = Image8x8::gradient(WHITE);
+    let top = Image::new_solid(RED);
+    let blended = bottom.blend(&top, BlendMode::Multiply, 1.0);
+    for (pixel, below) in blended.pixels().iter().zip(bottom.pixels().iter()) {
+        assert_eq!(*pixel, Color { r: below.r, g: 0, b: 0 });
+    }
+    let mut overlay = [RgbaColor::default(); 64];
+    overlay[9] = RgbaColor::from(BLUE);
+    let composited = bottom.composite(&overlay);
+    assert_eq!(composited[(1, 1)], BLUE);
+    assert_eq!(composited[(0, 1)], bottom[(0, 1)]);
+}
@@ -0,0 +1,82 @@
+//! Host tests of the color conversions.
+
+use tp_led_matrix::color::{Hsl, Hsv, ParseColorError, NAMED};
+use tp_led_matrix::image::{self, BLUE, GREEN, RED};
+use tp_led_matrix::Color;
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-3
+}
+
+#[test]
+fn hsv_primaries() {
+    assert_eq!(Color::from_hsv(Hsv { h: 0.0, s: 1.0, v: 1.0 }), RED);
+    assert_eq!(Color::from_hsv(Hsv { h: 120.0, s: 1.0, v: 1.0 }), GREEN);
+    assert_eq!(Color::from_hsv(Hsv { h: 240.0, s: 1.0, v: 1.0 }), BLUE);
+    assert_eq!(Color::from_hsv(Hsv { h: 60.0, s: 1.0, v: 1.0 }), image::YELLOW);
+    assert_eq!(Color::from_hsv(Hsv { h: 30.0, s: 0.0, v: 0.5 }), image::GRAY);
+    assert_eq!(BLUE.to_hsv(), Hsv { h: 240.0, s: 1.0, v: 1.0 });
+    assert_eq!(image::BLACK.to_hsv(), Hsv { h: 0.0, s: 0.0, v: 0.0 });
+}
+
+#[test]
+fn hue_wraps_around() {
+    let hsv = |h| Color::from_hsv(Hsv { h, s: 1.0, v: 1.0 });
+    assert_eq!(hsv(360.0), RED);
+    assert_eq!(hsv(-120.0), BLUE);
+    assert_eq!(hsv(720.0 + 120.0), GREEN);
+    assert_eq!(Color::from_hsv(Hsv { h: 0.0, s: 2.0, v: -1.0 }), image::BLACK);
+}
+
+#[test]
+fn hsl_values() {
+    assert_eq!(Color::from_hsl(Hsl { h: 0.0, s: 1.0, l: 0.5 }), RED);
+    assert_eq!(Color::from_hsl(Hsl { h: 300.0, s: 1.0, l: 0.25 }), image::PURPLE);
+    assert_eq!(Color::from_hsl(Hsl { h: 0.0, s: 1.0, l: 1.0 }), image::WHITE);
+    let hsl = image::ORANGE.to_hsl();
+    assert!(close(hsl.h, 38.8235) && close(hsl.s, 1.0) && close(hsl.l, 0.5), "{:?}", hsl);
+}
+
+#[test]
+fn every_color_round_trips() {
+    for r in (0..=255).step_by(5) {
+        for g in (0..=255).step_by(5) {
+            for b in (0..=255).step_by(5) {
+                let color = Color { r, g, b };
+                assert_eq!(Color::from_hsv(color.to_hsv()), color);
+                assert_eq!(Color::from_hsl(color.to_hsl()), color);
+            }
+        }
+    }
+}
+
+#[test]
+fn hex_values() {
+    let color = Color { r: 0x12, g: 0xab, b: 0xef };
+    assert_eq!(Color::from_hex(0x12abef), color);
+    assert_eq!(Color::from_hex(0xff12abef), color);
+    assert_eq!(color.to_hex(), 0x12abef);
+    assert_eq!(format!("#{:x}", color), "#12abef");
+    assert_eq!(format!("{:X}", color), "12ABEF");
+}
+
+#[test]
+fn parsing() {
+    assert_eq!("#12abef".parse(), Ok(Color::from_hex(0x12abef)));
+    assert_eq!("0x12ABEF".parse(), Ok(Color::from_hex(0x12abef)));
+    assert_eq!("Orange".parse(), Ok(image::ORANGE));
+    assert_eq!("#12abe".parse::<Color>(), Err(ParseColorError::InvalidLength(5)));
+    assert_eq!("#+2abef".parse::<Color>(), Err(ParseColorError::InvalidDigit));
+    assert_eq!("12abef".parse::<Color>(), Err(ParseColorError::UnknownName));
+}
+
+#[test]
+fn names_are_lowercase_and_unique() {
+    for (i, (name, color)) in NAMED.iter().enumerate() {
+        assert_eq!(name.to_lowercase(), *name);
+        assert!(NAMED[..i].iter().all(|(n, _)| n != name));
+        assert_eq!(Color::named(&name.to_uppercase()), Some(*color));
+        assert_eq!(format!("#{:x}", color).parse(), Ok(*color));
+    }
+    assert_eq!(Color::named("chartreuse"), None);
+}
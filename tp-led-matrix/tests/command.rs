@@ -0,0 +1,170 @@
+//! Host tests of the command channel.
+
+use tp_led_matrix::command::{Command, DisplayMode, Error, Parser, Reply, ReplyDecoder, Settings, Status, STATUS_LEN};
+use tp_led_matrix::dot_correction::WhiteBalance;
+use tp_led_matrix::gamma::Preset;
+use tp_led_matrix::protocol::{self, encode, encode_command, Decoder, Framing, Packet, CMD, ESC};
+use tp_led_matrix::{Color, Image};
+
+const ALL: [Command; 10] = [
+    Command::SetBrightness(0xfd),
+    Command::SetGamma(Preset::Linear),
+    Command::Fill(Color { r: 0xff, g: 0xfe, b: 0x00 }),
+    Command::Gradient(Color { r: 1, g: 2, b: 3 }),
+    Command::Pause,
+    Command::Resume,
+    Command::QueryStatus,
+    Command::Reset,
+    Command::SetWhiteBalance(WhiteBalance { red: 63, green: 40, blue: 0 }),
+    Command::SetDisplayMode(DisplayMode::Dithered),
+];
+
+fn parse(bytes: &[u8]) -> Vec<Result<Option<Command>, Error>> {
+    let mut parser = Parser::new();
+    bytes.iter().map(|&b| parser.push(b)).collect()
+}
+
+#[test]
+fn parser_reads_written_commands() {
+    for command in ALL {
+        let mut bytes = Vec::new();
+        command.write(|b| bytes.push(b));
+        let results = parse(&bytes);
+        assert_eq!(results.last(), Some(&Ok(Some(command))));
+        assert!(results[..results.len() - 1].iter().all(|r| *r == Ok(None)));
+    }
+}
+
+#[test]
+fn parser_rejects_unknown_opcode_and_recovers() {
+    assert_eq!(parse(&[0x42, 0x05]), vec![Err(Error::UnknownOpcode(0x42)), Ok(Some(Command::Pause))]);
+}
+
+#[test]
+fn parser_rejects_unknown_gamma_preset() {
+    assert_eq!(
+        parse(&[0x02, 0x09]),
+        vec![Ok(None), Err(Error::InvalidArgument { opcode: 0x02, value: 0x09 })]
+    );
+}
+
+#[test]
+fn parser_rejects_white_balance_above_max() {
+    assert_eq!(
+        parse(&[0x09, 10, 64, 20]),
+        vec![Ok(None), Ok(None), Ok(None), Err(Error::InvalidArgument { opcode: 0x09, value: 64 })]
+    );
+}
+
+#[test]
+fn decoder_multiplexes_commands_and_frames() {
+    let image = Image::gradient(Color { r: 0xfd, g: 0xfe, b: 0xff });
+    let mut bytes = Vec::new();
+    encode_command(&Command::SetBrightness(0xfe), |b| bytes.push(b));
+    encode(&image, Framing::Stuffed, |b| bytes.push(b));
+    encode_command(&Command::Fill(Color { r: 0xfd, g: 0, b: 0 }), |b| bytes.push(b));
+    assert_eq!(bytes[..4], [CMD, 0x01, ESC, 0xde]);
+
+    let mut decoder = Decoder::with_framing(Framing::Stuffed);
+    let mut received = Vec::new();
+    for b in bytes {
+        match decoder.push(b).unwrap() {
+            Some(Packet::Command(command)) => received.push(Some(command)),
+            Some(Packet::Image(decoded)) => {
+                assert_eq!(decoded.as_ref(), image.as_ref());
+                received.push(None);
+            }
+            Some(Packet::Hdr(_)) => panic!("unexpected 12-bit frame"),
+            None => {}
+        }
+    }
+    assert_eq!(
+        received,
+        vec![Some(Command::SetBrightness(0xfe)), None, Some(Command::Fill(Color { r: 0xfd, g: 0, b: 0 }))]
+    );
+    assert_eq!((decoder.stats().frames, decoder.stats().commands), (1, 2));
+}
+
+#[test]
+fn decoder_reports_invalid_commands() {
+    let mut decoder = Decoder::with_framing(Framing::Stuffed);
+    assert!(matches!(decoder.push(CMD), Ok(None)));
+    assert!(matches!(
+        decoder.push(0x99),
+        Err(protocol::Error::Command(Error::UnknownOpcode(0x99)))
+    ));
+    assert_eq!(decoder.stats().invalid_commands, 1);
+}
+
+#[test]
+fn legacy_decoder_treats_cmd_as_pixel() {
+    let mut decoder = Decoder::new();
+    let mut frames = 0;
+    for b in std::iter::once(protocol::SYNC).chain([CMD; 192]) {
+        if let Ok(Some(Packet::Image(image))) = decoder.push(b) {
+            assert_eq!(image.as_ref(), &[CMD; 192]);
+            frames += 1;
+        }
+    }
+    assert_eq!(frames, 1);
+}
+
+#[test]
+fn settings_follow_commands() {
+    let mut settings = Settings::default();
+    assert!(settings.apply(&Command::SetBrightness(10)));
+    assert!(settings.apply(&Command::SetGamma(Preset::Linear)));
+    assert!(settings.apply(&Command::Pause));
+    assert!(!settings.apply(&Command::Fill(Color::default())));
+    let white_balance = WhiteBalance { red: 50, green: 63, blue: 45 };
+    assert!(settings.apply(&Command::SetWhiteBalance(white_balance)));
+    assert!(settings.apply(&Command::SetDisplayMode(DisplayMode::Dithered)));
+    assert_eq!(
+        settings,
+        Settings { brightness: 10, gamma: Preset::Linear, paused: true, white_balance, mode: DisplayMode::Dithered }
+    );
+    assert!(settings.apply(&Command::Resume));
+    assert!(!settings.paused);
+}
+
+#[test]
+fn images_are_dropped_while_paused() {
+    let mut settings = Settings::default();
+    assert!(settings.shows_images());
+    settings.apply(&Command::Pause);
+    assert!(!settings.shows_images());
+    settings.apply(&Command::Resume);
+    assert!(settings.shows_images());
+}
+
+#[test]
+fn status_round_trips() {
+    let status = Status {
+        settings: Settings {
+            brightness: 0x80,
+            gamma: Preset::Standard,
+            paused: true,
+            white_balance: WhiteBalance { red: 63, green: 50, blue: 40 },
+            mode: DisplayMode::Dithered,
+        },
+        frames: 0x01020304,
+        errors: 7,
+    };
+    let bytes = status.to_bytes();
+    assert_eq!(bytes, [0x07, 1, 0x80, 1, 1, 2, 3, 4, 0, 0, 0, 7, 63, 50, 40, 1]);
+    assert_eq!(Status::from_bytes(&bytes), Some(status));
+    assert_eq!(Status::from_bytes(&[0; STATUS_LEN]), None);
+}
+
+#[test]
+fn status_bytes_are_not_acknowledgements() {
+    // Counters made of ACK and NACK bytes
+    let status = Status { settings: Settings::default(), frames: 0x0606_0606, errors: 0x1515_1515 };
+    let mut bytes = vec![CMD];
+    bytes.extend(status.to_bytes());
+    bytes.extend([protocol::NACK, 0x42, protocol::ACK]);
+    let mut replies = ReplyDecoder::new();
+    let received: Vec<_> = bytes.into_iter().filter_map(|b| replies.push(b)).collect();
+    assert_eq!(received, vec![Reply::Status(status), Reply::Nack, Reply::Ack]);
+}
+
@@ -0,0 +1,185 @@
+//! Decode small images of every format and check the resizing filters.
+
+use tp_led_matrix::convert::{self, Filter, Frame, RgbImage};
+use tp_led_matrix::gamma;
+use tp_led_matrix::image::{BLUE, RED};
+use tp_led_matrix::Color;
+
+/// 16×16 image whose red channel grows by 16 every column and green channel
+/// by 16 every row.
+fn ramp() -> RgbImage {
+    let pixels = (0..16)
+        .flat_map(|y| (0..16).map(move |x| Color { r: x * 16, g: y * 16, b: 0x42 }))
+        .collect();
+    RgbImage::new(16, 16, pixels)
+}
+
+fn color(r: u8, g: u8, b: u8) -> Color {
+    Color { r, g, b }
+}
+
+#[test]
+fn nearest_takes_cell_centers() {
+    let image = convert::resize(&ramp(), Filter::Nearest);
+    assert_eq!(image[(2, 5)], color(5 * 32 + 16, 2 * 32 + 16, 0x42));
+}
+
+#[test]
+fn box_and_bilinear_average_cells() {
+    for filter in [Filter::Box, Filter::Bilinear] {
+        let image = convert::resize(&ramp(), filter);
+        for row in 0..8 {
+            for col in 0..8 {
+                assert_eq!(image[(row, col)], color(col as u8 * 32 + 8, row as u8 * 32 + 8, 0x42), "{:?}", filter);
+            }
+        }
+    }
+}
+
+#[test]
+fn hdr_resizing_keeps_averages() {
+    // Every cell averages two columns whose red channels differ by 1, the
+    // half step is kept with 12 bits
+    let source = RgbImage::new(16, 16, (0..256).map(|i| color(i as u8 % 16, 0, 255)).collect());
+    let image = convert::resize_hdr(&source, Filter::Box);
+    assert_eq!((image[(3, 2)].r, image[(3, 2)].b), (72, 4095));
+    assert_eq!(convert::resize(&source, Filter::Box)[(3, 2)].r, 5);
+}
+
+#[test]
+fn upscaling_repeats_pixels() {
+    let source = RgbImage::new(2, 1, vec![RED, BLUE]);
+    for filter in [Filter::Nearest, Filter::Box] {
+        let image = convert::resize(&source, filter);
+        for row in 0..8 {
+            assert_eq!(image.row(row)[..4], [RED; 4]);
+            assert_eq!(image.row(row)[4..], [BLUE; 4]);
+        }
+    }
+    let image = convert::resize(&source, Filter::Bilinear);
+    assert_eq!(image[(0, 0)], RED);
+    assert_eq!(image[(0, 7)], BLUE);
+    assert_eq!(image[(0, 4)], color(0x60, 0, 0x9f));
+}
+
+#[test]
+fn ascii_ppm() {
+    let data = b"P3\n# two pixels\n2 1\n15\n15 0 0  0 15 7\n";
+    let image = convert::read_ppm(data).unwrap();
+    assert_eq!(image, RgbImage::new(2, 1, vec![color(255, 0, 0), color(0, 255, 119)]));
+}
+
+#[test]
+fn binary_ppm() {
+    let mut data = b"P6 1 2 65535\n".to_vec();
+    data.extend([0xff, 0xff, 0x80, 0x00, 0x00, 0x00]);
+    data.extend([0x00, 0x00, 0x00, 0x00, 0xff, 0xff]);
+    let image = convert::read_ppm(&data).unwrap();
+    assert_eq!(image.pixels, vec![color(255, 128, 0), color(0, 0, 255)]);
+    assert!(convert::read_ppm(&data[..data.len() - 1]).is_err());
+}
+
+#[test]
+fn huge_ppm_header_is_rejected() {
+    let error = convert::read_ppm(b"P6 4294967296 4294967296 255\n\0\0\0").unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    assert!(convert::read_ppm(b"P3 4294967296 4294967296 255\n1 2 3").is_err());
+}
+
+#[test]
+fn png_alpha_over_black() {
+    let mut data = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut data, 2, 1);
+        encoder.set_color(png::ColorType::Rgba);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[10, 20, 30, 255, 200, 200, 200, 0]).unwrap();
+    }
+    let frames = convert::read(&data).unwrap();
+    let expected = RgbImage::new(2, 1, vec![color(10, 20, 30), Color::default()]);
+    assert_eq!(frames, vec![Frame { image: expected, delay_ms: None }]);
+}
+
+#[test]
+fn gif_frames_are_composed() {
+    let mut data = Vec::new();
+    {
+        let palette = [0, 0, 0, 255, 0, 0, 0, 0, 255];
+        let mut encoder = gif::Encoder::new(&mut data, 2, 1, &palette).unwrap();
+        let mut first = gif::Frame::from_indexed_pixels(2, 1, &[1, 1], None);
+        first.delay = 5;
+        encoder.write_frame(&first).unwrap();
+        // Second frame covering only the right pixel, without delay
+        let mut second = gif::Frame::from_indexed_pixels(1, 1, &[2], None);
+        second.left = 1;
+        encoder.write_frame(&second).unwrap();
+    }
+    let frames = convert::read(&data).unwrap();
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0].image.pixels, vec![RED, RED]);
+    assert_eq!(frames[0].delay_ms, Some(50));
+    assert_eq!(frames[1].image.pixels, vec![RED, BLUE]);
+    assert_eq!(frames[1].delay_ms, None);
+}
+
+/// CRC-32 of PNG chunks.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { crc >> 1 ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+#[test]
+fn empty_images_are_rejected() {
+    let invalid = |result: std::io::Result<Vec<Frame>>| result.unwrap_err().kind() == std::io::ErrorKind::InvalidData;
+    assert!(invalid(convert::read(b"P3 0 0 255\n")));
+    assert!(invalid(convert::read(b"P6 2 0 255\n")));
+
+    // A 1×1 PNG whose header is patched to a width of 0
+    let mut data = Vec::new();
+    png::Encoder::new(&mut data, 1, 1).write_header().unwrap().write_image_data(&[0]).unwrap();
+    data[16..20].fill(0);
+    let crc = crc32(&data[12..29]);
+    data[29..33].copy_from_slice(&crc.to_be_bytes());
+    assert!(invalid(convert::read(&data)));
+
+    let palette = [0, 0, 0, 255, 0, 0];
+    let mut data = Vec::new();
+    {
+        let mut encoder = gif::Encoder::new(&mut data, 0, 0, &palette).unwrap();
+        encoder.write_frame(&gif::Frame::from_indexed_pixels(0, 0, &[], None)).unwrap();
+    }
+    assert!(invalid(convert::read(&data)));
+}
+
+#[test]
+fn gif_frames_without_columns_are_empty() {
+    let palette = [0, 0, 0, 255, 0, 0];
+    let mut data = Vec::new();
+    {
+        let mut encoder = gif::Encoder::new(&mut data, 2, 1, &palette).unwrap();
+        encoder.write_frame(&gif::Frame::from_indexed_pixels(2, 1, &[1, 1], None)).unwrap();
+        encoder.write_frame(&gif::Frame::from_indexed_pixels(0, 1, &[], None)).unwrap();
+    }
+    let frames = convert::read(&data).unwrap();
+    assert_eq!(frames[1].image.pixels, vec![RED, RED]);
+}
+
+#[test]
+fn unknown_format() {
+    assert!(convert::read(b"BM not supported").is_err());
+}
+
+#[test]
+fn gamma_uncorrect_is_minimal_inverse() {
+    for y in 0..=255 {
+        let x = gamma::gamma_uncorrect(y);
+        assert!(gamma::gamma_correct(x) >= y);
+        assert!(x == 0 || gamma::gamma_correct(x - 1) < y);
+    }
+}
@@ -0,0 +1,68 @@
+//! Host tests of the dot-correction values.
+
+use tp_led_matrix::dot_correction::{DotCorrection, WhiteBalance, MAX};
+use tp_led_matrix::gamma::Channel;
+use tp_led_matrix::layout::PanelLayout;
+
+#[test]
+fn values_are_limited() {
+    assert_eq!(DotCorrection::uniform(64, 255, 63), DotCorrection::FULL);
+    let mut dot_correction = DotCorrection::default();
+    dot_correction.set(3, Channel::Green, 200);
+    assert_eq!(dot_correction.get(3, Channel::Green), MAX);
+    assert_eq!(WhiteBalance::new(63, 64, 0), None);
+}
+
+#[test]
+fn scaling_rounds_to_nearest() {
+    let dot_correction = DotCorrection::uniform(63, 40, 1);
+    assert_eq!(dot_correction.scaled(255), dot_correction);
+    assert_eq!(dot_correction.scaled(0), DotCorrection::uniform(0, 0, 0));
+    assert_eq!(dot_correction.scaled(128), DotCorrection::uniform(32, 20, 1));
+}
+
+#[test]
+fn shift_order_is_bgr_from_the_last_column() {
+    let mut dot_correction = DotCorrection::uniform(0, 0, 0);
+    dot_correction.set(7, Channel::Blue, 1);
+    dot_correction.set(7, Channel::Green, 2);
+    dot_correction.set(0, Channel::Red, 3);
+    let values = dot_correction.shift_order(&PanelLayout::DEFAULT);
+    assert_eq!(values[..3], [1, 2, 0]);
+    assert_eq!(values[23], 3);
+}
+
+#[test]
+fn white_balance_round_trips() {
+    let white_balance = WhiteBalance::new(63, 48, 52).unwrap();
+    let bytes = white_balance.to_bytes();
+    assert_eq!(WhiteBalance::from_bytes(&bytes), Some(white_balance));
+    assert_eq!(WhiteBalance::from_bytes(&[0xff; 4]), None);
+    let mut corrupted = bytes;
+    corrupted[1] ^= 1;
+    assert_eq!(WhiteBalance::from_bytes(&corrupted), None);
+    assert_eq!(white_balance.dot_correction(), DotCorrection::uniform(63, 48, 52));
+}
+
+#[test]
+fn white_balance_records_fill_a_double_word() {
+    let white_balance = WhiteBalance::new(10, 20, 30).unwrap();
+    let record = white_balance.to_record();
+    assert_eq!(record[4..], [0xff; 4]);
+    assert_eq!(WhiteBalance::from_record(&record), Some(white_balance));
+    // Erased flash
+    assert_eq!(WhiteBalance::from_record(&[0xff; 8]), None);
+}
+
+#[test]
+fn bank0_packs_six_bits_per_value() {
+    assert_eq!(DotCorrection::FULL.to_bank0(&PanelLayout::DEFAULT), [0xff; 18]);
+    let mut dot_correction = DotCorrection::uniform(0, 0, 0);
+    dot_correction.set(7, Channel::Blue, 0b100001);
+    dot_correction.set(7, Channel::Green, 0b110000);
+    dot_correction.set(0, Channel::Red, 0b000011);
+    let bytes = dot_correction.to_bank0(&PanelLayout::DEFAULT);
+    assert_eq!(bytes[..2], [0b1000_0111, 0b0000_0000]);
+    assert_eq!(bytes[2..17], [0; 15]);
+    assert_eq!(bytes[17], 0b0000_0011);
+}
@@ -0,0 +1,185 @@
+//! Host tests of the drawing primitives, compared with ASCII art where `#`
+//! is a drawn pixel.
+
+use tp_led_matrix::draw::{Canvas, FillError, FILL_STACK_LEN};
+use tp_led_matrix::image::{BLUE, RED, WHITE};
+use tp_led_matrix::{Color, Image, Image8x8};
+
+fn render(image: &Image) -> String {
+    let mut art = String::new();
+    for row in 0..8 {
+        for col in 0..8 {
+            art.push(if image[(row, col)] == Color::default() { '.' } else { '#' });
+        }
+        art.push('\n');
+    }
+    art
+}
+
+fn draw(f: impl FnOnce(&mut Image)) -> String {
+    let mut image = Image::default();
+    f(&mut image);
+    render(&image)
+}
+
+#[test]
+fn set_pixel_is_bounds_checked() {
+    let mut image = Image8x8::default();
+    assert!(image.set_pixel(7, 2, RED));
+    assert!(!image.set_pixel(8, 2, RED));
+    assert!(!image.set_pixel(-1, 0, RED));
+    assert_eq!(image[(2, 7)], RED);
+    assert_eq!(image.get_pixel(7, 2), Some(RED));
+    assert_eq!(image.get_pixel(0, 8), None);
+}
+
+#[test]
+fn straight_lines_are_clipped() {
+    let art = draw(|image| {
+        image.hline(5, -3, 1, WHITE);
+        image.vline(6, 10, 4, WHITE);
+        image.hline(0, 7, 9, WHITE);
+    });
+    assert_eq!(art, "\
+........
+######..
+........
+........
+......#.
+......#.
+......#.
+......#.
+");
+}
+
+#[test]
+fn bresenham_lines() {
+    let art = draw(|image| image.line(0, 0, 7, 3, WHITE));
+    assert_eq!(art, "\
+##......
+..##....
+....##..
+......##
+........
+........
+........
+........
+");
+    assert_eq!(draw(|image| image.line(7, 3, 0, 0, WHITE)), art);
+    let steep = draw(|image| image.line(1, -2, 3, 9, WHITE));
+    assert_eq!(steep.lines().filter(|l| l.contains('#')).count(), 8);
+}
+
+#[test]
+fn rectangles() {
+    let art = draw(|image| {
+        image.rect(1, 1, 4, 3, WHITE);
+        image.fill_rect(6, 5, 5, 5, WHITE);
+        image.rect(0, 0, 0, 3, WHITE);
+    });
+    assert_eq!(art, "\
+........
+.####...
+.#..#...
+.####...
+........
+......##
+......##
+......##
+");
+}
+
+#[test]
+fn circles() {
+    let outline = draw(|image| image.circle(3, 3, 3, WHITE));
+    assert_eq!(outline, "\
+..###...
+.#...#..
+#.....#.
+#.....#.
+#.....#.
+.#...#..
+..###...
+........
+");
+    let filled = draw(|image| image.fill_circle(3, 3, 3, WHITE));
+    assert_eq!(filled, "\
+..###...
+.#####..
+#######.
+#######.
+#######.
+.#####..
+..###...
+........
+");
+    assert_eq!(draw(|image| image.circle(0, 0, 0, WHITE)), draw(|image| { image.set_pixel(0, 0, WHITE); }));
+}
+
+#[test]
+fn huge_circles_are_clipped() {
+    let (min, max) = (i32::MIN, i32::MAX);
+    // The top of a circle centered far below the canvas
+    assert_eq!(draw(|image| image.circle(3, max, max - 7, WHITE)), draw(|image| image.hline(0, 7, 7, WHITE)));
+    assert_eq!(draw(|image| image.fill_circle(3, max, max - 6, WHITE)), draw(|image| image.fill_rect(0, 6, 8, 2, WHITE)));
+    assert_eq!(draw(|image| image.fill_circle(min, min, max, WHITE)), draw(|_| {}));
+    assert_eq!(draw(|image| image.fill_circle(0, 0, max, WHITE)), draw(|image| image.fill(WHITE)));
+    assert_eq!(draw(|image| image.circle(0, 0, max, WHITE)), draw(|_| {}));
+    assert_eq!(draw(|image| image.circle(max, max, 0, WHITE)), draw(|_| {}));
+}
+
+#[test]
+fn flood_fill_stays_in_the_region() {
+    let mut image = Image::default();
+    image.rect(0, 0, 5, 5, WHITE);
+    image.set_pixel(6, 6, BLUE);
+    assert_eq!(image.flood_fill(2, 2, BLUE), Ok(()));
+    assert_eq!(image[(2, 2)], BLUE);
+    assert_eq!(image[(1, 3)], BLUE);
+    assert_eq!(image[(0, 0)], WHITE);
+    assert_eq!(image[(6, 7)], Color::default());
+    assert_eq!(image.flood_fill(7, 7, RED), Ok(()));
+    assert!(!render(&image).contains('.'));
+    assert_eq!(image[(2, 2)], BLUE);
+    assert_eq!(image[(6, 6)], BLUE);
+    assert_eq!(image[(0, 7)], RED);
+}
+
+#[test]
+fn flood_fill_reports_a_full_stack() {
+    // Every other column hangs from the top row, so the first span has one
+    // span below it per column
+    let mut comb = Image::<{ 4 * FILL_STACK_LEN }, 2>::default();
+    for col in (1..4 * FILL_STACK_LEN as i32).step_by(2) {
+        comb.set_pixel(col, 1, WHITE);
+    }
+    assert_eq!(comb.flood_fill(0, 0, RED), Err(FillError::StackOverflow));
+    assert_eq!(comb.get_pixel(5, 0), Some(RED));
+}
+
+#[test]
+fn huge_coordinates_are_clipped() {
+    let (min, max) = (i32::MIN, i32::MAX);
+    assert_eq!(draw(|image| image.line(min, min, max, max, WHITE)), draw(|image| image.line(0, 0, 7, 7, WHITE)));
+    assert_eq!(draw(|image| image.line(max, 3, min, 3, WHITE)), draw(|image| image.hline(0, 7, 3, WHITE)));
+    assert_eq!(draw(|image| image.fill_rect(min, min, max, max, WHITE)), draw(|image| image.fill_rect(0, 0, 0, 0, WHITE)));
+    assert_eq!(draw(|image| image.fill_rect(-3, -3, max, max, WHITE)), draw(|image| image.fill(WHITE)));
+    assert_eq!(draw(|image| image.rect(2, 2, max, max, WHITE)), draw(|image| {
+        image.hline(2, 7, 2, WHITE);
+        image.vline(2, 2, 7, WHITE);
+    }));
+}
+
+#[test]
+fn blit_clips() {
+    let mut sprite = Image8x8::default();
+    sprite.fill_rect(0, 0, 2, 2, RED);
+    let mut image = Image8x8::default();
+    image.blit(&sprite, 6, -1);
+    let mut expected = Image::default();
+    expected.hline(6, 7, 0, RED);
+    assert_eq!(image.0, expected.0);
+    let mut whole = Image8x8::new_solid(BLUE);
+    whole.blit(&Image8x8::gradient(WHITE), 0, 0);
+    assert_eq!(whole.0, Image::gradient(WHITE).0);
+}
@@ -0,0 +1,216 @@
+//! Host tests of the effects: golden frames, and properties of their
+//! building blocks.
+
+use tp_led_matrix::effect::{heat_color, sin8, wheel, Effect, Fire, Life, Plasma, Rainbow, Rng, Twinkle};
+use tp_led_matrix::image::{BLACK, BLUE, GREEN, RED, WHITE};
+use tp_led_matrix::Image;
+
+/// Rows of hexadecimal colors, one per line.
+fn dump(image: &Image) -> String {
+    let mut text = String::new();
+    for row in 0..8 {
+        let colors: Vec<_> = image.row(row).iter().map(|color| format!("{:x}", color)).collect();
+        text += &colors.join(" ");
+        text += "\n";
+    }
+    text
+}
+
+/// Render `effect` every `step` milliseconds until `t` included, returning
+/// the last frame.
+fn run(effect: &mut impl Effect, t: u32, step: u32) -> Image {
+    let mut image = Image::default();
+    for t in (0..=t).step_by(step as usize) {
+        effect.render(t, &mut image);
+    }
+    image
+}
+
+/// Cells of a glider moving down and right, in the top-left corner.
+const GLIDER: u64 = 0b010 | 0b100 << 8 | 0b111 << 16;
+
+const GOLDEN_PLASMA0: &str = "\
+007e81 1800e7 60009f 5a00a5 0000ff 006699 009f60 00a857
+0600f9 90006f c60039 ae0051 4800b7 0030cf 007887 00906f
+5a00a5 d2002d f60009 d2002d 5a00a5 0030cf 008778 009f60
+780087 de0021 f60009 c0003f 3600c9 00609f 00b748 00c03f
+60009f ba0045 c0003f 780087 001ee1 00b748 00ff00 00f609
+1800e7 60009f 5700a8 0000ff 009966 21de00 5aa500 42bd00
+0057a8 001ee1 0039c6 00906f 18e700 906f00 b74800 906f00
+00b44b 008d72 00a55a 00ed12 669900 cc3300 e41b00 ae5100
+";
+
+const GOLDEN_PLASMA_1S: &str = "\
+7e0081 1500ea 0027d8 0033cc 000ff0 1b00e4 1800e7 001be4
+bd0042 4200bd 0009f6 0024db 0003fc 3600c9 4200bd 1e00e1
+c90036 3f00c0 001ee1 003cc3 000cf3 3f00c0 5a00a5 4500ba
+9f0060 0600f9 005aa5 006996 002ad5 3000cf 5d00a2 5a00a5
+4200bd 005aa5 00ab54 00ab54 005aa5 0f00f0 4b00b4 5700a8
+0042bd 00cf30 0ff000 00ff00 009f60 0024db 2700d8 4200bd
+00ba45 39c600 699600 48b700 00d827 004eb1 0f00f0 2d00d2
+03fc00 6f9000 906f00 609f00 00de21 0045ba 1800e7 2a00d5
+";
+
+const GOLDEN_FIRE: &str = "\
+000000 210000 de0000 000000 4e0000 030000 ff6300 060000
+000000 270000 f30000 000000 ff3000 000000 ff3c00 480000
+000000 2a0000 ed0000 000000 ff5700 870000 ae0000 bd0000
+000000 090000 ff4b00 330000 ff6c00 540000 bd0000 ff7500
+000000 000000 ff5a00 ff0600 ffc000 090000 960000 ffa800
+000000 000000 ff4200 ff7e00 ffff09 660000 ff8700 ffff60
+000000 000000 ff3f00 ff8d00 ffff1e 990000 ffff15 ffffc3
+000000 000000 ff3f00 ff8d00 ffff1e 990000 ffff15 ffffff
+";
+
+const GOLDEN_TWINKLE: &str = "\
+000000 000000 000000 00121d db2400 000000 000000 000000
+004f76 000000 000000 000b44 000000 000000 000000 001709
+000000 000000 000000 000000 000d39 000000 000000 000000
+000000 000000 000000 000000 3a00a6 000000 000000 000000
+000000 000000 000000 000000 000000 000000 000000 000000
+000000 000000 000000 000000 001088 000000 000000 000000
+000000 000000 000000 07001e 000000 000000 000000 000000
+040013 000000 000000 000000 000000 000000 000000 000e68
+";
+
+const GOLDEN_LIFE: &str = "\
+000000 00ff00 00ff00 000000 00ff00 00ff00 000000 000000
+000000 000000 000000 00ff00 00ff00 000000 000000 00ff00
+000000 000000 000000 000000 00ff00 000000 000000 000000
+000000 000000 00ff00 000000 000000 00ff00 000000 000000
+00ff00 000000 000000 000000 000000 000000 000000 000000
+000000 000000 00ff00 00ff00 000000 000000 000000 00ff00
+000000 000000 000000 00ff00 000000 000000 000000 00ff00
+000000 00ff00 00ff00 00ff00 00ff00 000000 00ff00 000000
+";
+
+#[test]
+fn sin8_follows_a_sine() {
+    assert_eq!([sin8(0), sin8(64), sin8(128), sin8(192)], [128, 255, 128, 1]);
+    for theta in 0..=255u8 {
+        assert_eq!(sin8(theta) as i32 - 128, 128 - sin8(theta.wrapping_add(128)) as i32);
+        let exact = 128.0 + 127.0 * (theta as f32 * core::f32::consts::PI / 128.0).sin();
+        assert!((sin8(theta) as f32 - exact).abs() < 8.0, "sin8({})", theta);
+    }
+}
+
+#[test]
+fn wheel_goes_through_primaries() {
+    assert_eq!([wheel(0), wheel(85), wheel(170)], [RED, GREEN, BLUE]);
+    for position in 0..=255u8 {
+        let color = wheel(position);
+        assert_eq!(color.r as u32 + color.g as u32 + color.b as u32, 255);
+    }
+}
+
+#[test]
+fn heat_goes_from_black_to_white() {
+    assert_eq!([heat_color(0), heat_color(255)], [BLACK, WHITE]);
+    assert_eq!(heat_color(85), RED);
+}
+
+#[test]
+fn rng_is_reproducible() {
+    let values = |seed| {
+        let mut rng = Rng::new(seed);
+        [rng.next_u32(), rng.next_u32(), rng.next_u32()]
+    };
+    assert_eq!(values(1), values(1));
+    assert_ne!(values(1), values(2));
+    assert_ne!(values(0), [0; 3]);
+    let mut rng = Rng::new(3);
+    assert!((0..1000).all(|_| rng.below(10) < 10));
+    assert_eq!(rng.below(0), 0);
+}
+
+#[test]
+fn plasma_matches_golden_frames() {
+    assert_eq!(dump(&run(&mut Plasma::default(), 0, 1)), GOLDEN_PLASMA0);
+    assert_eq!(dump(&run(&mut Plasma::default(), 1000, 1000)), GOLDEN_PLASMA_1S);
+}
+
+#[test]
+fn rainbow_bands_are_diagonal() {
+    let mut rainbow = Rainbow::default();
+    let image = run(&mut rainbow, 500, 500);
+    for row in 0..8 {
+        for col in 0..8 {
+            assert_eq!(image[(row, col)], wheel((50 + 16 * (row + col)) as u8));
+        }
+    }
+}
+
+#[test]
+fn fire_matches_golden_frame() {
+    assert_eq!(dump(&run(&mut Fire::new(1), 1000, 10)), GOLDEN_FIRE);
+}
+
+#[test]
+fn twinkle_matches_golden_frame() {
+    assert_eq!(dump(&run(&mut Twinkle::new(7), 1000, 10)), GOLDEN_TWINKLE);
+}
+
+#[test]
+fn life_matches_golden_frame() {
+    assert_eq!(dump(&run(&mut Life::random(42, GREEN), 1000, 10)), GOLDEN_LIFE);
+}
+
+#[test]
+fn stateful_effects_catch_up_with_time() {
+    assert_eq!(run(&mut Fire::new(5), 300, 10), run(&mut Fire::new(5), 300, 300));
+    assert_eq!(run(&mut Twinkle::new(5), 500, 50), run(&mut Twinkle::new(5), 500, 250));
+    assert_ne!(run(&mut Fire::new(5), 300, 10), run(&mut Fire::new(6), 300, 10));
+}
+
+#[test]
+fn effects_go_on_when_time_wraps() {
+    let mut life = Life::new(GLIDER, 1, RED);
+    let mut image = Image::default();
+    life.render(u32::MAX - 100, &mut image);
+    assert_eq!(life.cells(), GLIDER);
+    // 251 milliseconds later
+    life.render(150, &mut image);
+    assert_eq!(life.cells(), Life::next_generation(GLIDER));
+    life.render(400, &mut image);
+    assert_eq!(life.cells(), Life::next_generation(Life::next_generation(GLIDER)));
+}
+
+#[test]
+fn glider_crosses_the_torus() {
+    let mut cells = GLIDER;
+    for _ in 0..4 {
+        cells = Life::next_generation(cells);
+    }
+    // Moved one cell down and right
+    assert_eq!(cells, GLIDER << 9);
+    for _ in 4..32 {
+        cells = Life::next_generation(cells);
+    }
+    assert_eq!(cells, GLIDER);
+}
+
+#[test]
+fn life_draws_live_cells() {
+    let mut life = Life::new(GLIDER, 1, RED);
+    let mut image = Image::default();
+    life.render(0, &mut image);
+    assert_eq!(image[(0, 1)], RED);
+    assert_eq!(image[(0, 0)], BLACK);
+    assert_eq!(image.pixels().iter().filter(|&&pixel| pixel == RED).count(), 5);
+    life.render(250, &mut image);
+    assert_eq!(life.cells(), Life::next_generation(GLIDER));
+}
+
+#[test]
+fn frozen_life_restarts() {
+    // A block never changes
+    let block = 0b11 | 0b11 << 8;
+    assert_eq!(Life::next_generation(block), block);
+    let mut life = Life::new(block, 9, GREEN);
+    run(&mut life, 250, 250);
+    assert_ne!(life.cells(), block);
+
+    let mut empty = Life::new(0, 9, GREEN);
+    run(&mut empty, 250, 250);
+    assert_ne!(empty.cells(), 0);
+}
@@ -0,0 +1,91 @@
+//! Host tests of the bitmap font and of the scrolling text.
+
+use tp_led_matrix::font::{draw_char, draw_text, glyph, text_width, Marquee, ADVANCE};
+use tp_led_matrix::image::{BLUE, WHITE};
+use tp_led_matrix::{Color, Image};
+
+fn render(image: &Image) -> String {
+    let mut art = String::new();
+    for row in 0..8 {
+        for col in 0..8 {
+            art.push(if image[(row, col)] == WHITE { '#' } else { '.' });
+        }
+        art.push('\n');
+    }
+    art
+}
+
+#[test]
+fn glyphs_fit_in_seven_rows() {
+    for c in ' '..='~' {
+        assert!(glyph(c).iter().all(|&column| column < 0x80), "{:?}", c);
+        assert_eq!(glyph(c).iter().any(|&column| column != 0), c != ' ', "{:?}", c);
+    }
+    for c in ['°', '♥', '→', '█'] {
+        assert_ne!(glyph(c), glyph('\u{1}'), "{:?}", c);
+    }
+    assert_eq!(glyph('é'), glyph('\u{1}'));
+}
+
+#[test]
+fn glyphs_are_distinct() {
+    let glyphs: Vec<_> = ('!'..='~').map(glyph).collect();
+    for (i, g) in glyphs.iter().enumerate() {
+        assert!(!glyphs[..i].contains(g), "{:?}", (b'!' + i as u8) as char);
+    }
+}
+
+#[test]
+fn letter_a() {
+    let mut image = Image::default();
+    assert_eq!(draw_char(&mut image, 'A', 1, WHITE, BLUE), 1 + ADVANCE);
+    assert_eq!(render(&image), "\
+..###...
+.#...#..
+.#...#..
+.#...#..
+.#####..
+.#...#..
+.#...#..
+........
+");
+    // The background covers the glyph cell only
+    assert_eq!(image[(7, 6)], BLUE);
+    assert_eq!(image[(0, 0)], Color::default());
+    assert_eq!(image[(0, 7)], Color::default());
+}
+
+#[test]
+fn text_is_clipped() {
+    let mut image = Image::default();
+    assert_eq!(text_width("Hi!"), 18);
+    assert_eq!(draw_text(&mut image, "Hi!", -3, WHITE, BLUE), 15);
+    assert_eq!(render(&image), "\
+.#...#..
+.#......
+.#..##..
+##...#..
+.#...#..
+.#...#..
+.#..###.
+........
+");
+}
+
+#[test]
+fn marquee_scrolls_one_column_per_frame() {
+    let frames: Vec<Image> = Marquee::new("Hi", WHITE, BLUE).collect();
+    assert_eq!(frames.len(), 8 + 2 * ADVANCE as usize - 1);
+    assert!(frames[0].pixels().iter().all(|&pixel| pixel == BLUE));
+    for (n, frame) in frames.iter().enumerate() {
+        let mut expected = Image::new_solid(BLUE);
+        draw_text(&mut expected, "Hi", 8 - n as i32, WHITE, BLUE);
+        assert_eq!(frame.0, expected.0);
+    }
+    // The stem of 'i' with its dot leaves two frames before the end, and
+    // its last column is empty
+    assert_eq!(frames[frames.len() - 3][(0, 0)], WHITE);
+    assert!(frames[frames.len() - 1].pixels().iter().all(|&pixel| pixel == BLUE));
+    assert_eq!(Marquee::new("", WHITE, BLUE).count(), 0);
+    assert_eq!(Marquee::new("abc", WHITE, BLUE).len(), 25);
+}
@@ -0,0 +1,91 @@
+//! Host tests of the gamma curves.
+
+use tp_led_matrix::gamma::{gamma_correct, Channel, GammaCurve, Preset};
+use tp_led_matrix::Color;
+
+const CHANNELS: [Channel; 3] = [Channel::Red, Channel::Green, Channel::Blue];
+
+/// Computed at compile time.
+const POWER: GammaCurve = GammaCurve::power(2.2);
+
+fn curves() -> Vec<GammaCurve> {
+    vec![
+        GammaCurve::IDENTITY,
+        GammaCurve::STANDARD,
+        GammaCurve::SRGB,
+        POWER,
+        GammaCurve::power(1.8),
+        GammaCurve::power(0.5),
+        GammaCurve::power_rgb(2.0, 2.4, 2.8),
+    ]
+}
+
+#[test]
+fn curves_are_monotonic() {
+    for curve in curves() {
+        for channel in CHANNELS {
+            let table = curve.table(channel);
+            assert!(table.windows(2).all(|w| w[0] <= w[1]), "{:?} of {:?}", channel, curve);
+        }
+    }
+}
+
+#[test]
+fn curves_keep_endpoints() {
+    for curve in curves() {
+        for channel in CHANNELS {
+            let table = curve.table(channel);
+            assert_eq!((table[0], table[255]), (0, 255), "{:?} of {:?}", channel, curve);
+        }
+    }
+}
+
+#[test]
+fn identity_and_unit_power() {
+    let identity: Vec<u8> = (0..=255).collect();
+    assert_eq!(GammaCurve::IDENTITY.table(Channel::Green)[..], identity[..]);
+    assert_eq!(GammaCurve::power(1.0), GammaCurve::IDENTITY);
+    assert_eq!(Preset::Linear.curve(), GammaCurve::IDENTITY);
+}
+
+#[test]
+fn standard_is_gamma_correct() {
+    let expected: Vec<u8> = (0..=255).map(gamma_correct).collect();
+    for channel in CHANNELS {
+        assert_eq!(GammaCurve::STANDARD.table(channel)[..], expected[..]);
+    }
+    assert_eq!(GammaCurve::default(), GammaCurve::STANDARD);
+    assert_eq!(Preset::Standard.curve(), GammaCurve::STANDARD);
+}
+
+#[test]
+fn power_values() {
+    // (128 / 255)^2.2 = 0.2195, (64 / 255)^2.2 = 0.0478
+    assert_eq!(POWER.table(Channel::Red)[128], 56);
+    assert_eq!(POWER.table(Channel::Red)[64], 12);
+    assert_eq!(GammaCurve::power(0.5).table(Channel::Blue)[64], 128);
+    assert_eq!(POWER, GammaCurve::power_rgb(2.2, 2.2, 2.2));
+}
+
+#[test]
+fn srgb_values() {
+    // Linear segment, then ((c + 0.055) / 1.055)^2.4
+    assert_eq!(GammaCurve::SRGB.table(Channel::Red)[10], 1);
+    assert_eq!(GammaCurve::SRGB.table(Channel::Red)[128], 55);
+    assert_eq!(GammaCurve::SRGB.table(Channel::Red)[188], 128);
+}
+
+#[test]
+fn channels_are_independent() {
+    let curve = GammaCurve::power_rgb(1.0, 2.0, 0.5);
+    let corrected = curve.correct(Color { r: 64, g: 64, b: 64 });
+    assert_eq!(corrected, Color { r: 64, g: 16, b: 128 });
+}
+
+#[test]
+fn preset_ids() {
+    for preset in [Preset::Linear, Preset::Standard, Preset::Srgb] {
+        assert_eq!(Preset::from_id(preset.id()), Some(preset));
+    }
+    assert_eq!(Preset::from_id(3), None);
+}
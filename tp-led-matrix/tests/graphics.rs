@@ -0,0 +1,60 @@
+//! Rendering into an `Image` with embedded-graphics.
+
+use embedded_graphics::mono_font::ascii::FONT_4X6;
+use embedded_graphics::mono_font::MonoTextStyle;
+use embedded_graphics::pixelcolor::Rgb888;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::{Circle, Line, PrimitiveStyle, Rectangle};
+use embedded_graphics::text::Text;
+use tp_led_matrix::draw::Canvas;
+use tp_led_matrix::image::{BLUE, RED};
+use tp_led_matrix::{Color, Image, Image8x8};
+
+#[test]
+fn color_conversion() {
+    let color = Color { r: 1, g: 2, b: 3 };
+    assert_eq!(Rgb888::from(color), Rgb888::new(1, 2, 3));
+    assert_eq!(Color::from(Rgb888::new(1, 2, 3)), color);
+    assert_eq!(Color::from(Rgb888::RED), RED);
+}
+
+#[test]
+fn size_and_clear() {
+    let mut image = Image8x8::default();
+    assert_eq!(image.size(), Size::new(8, 8));
+    image.clear(Rgb888::BLUE).unwrap();
+    assert_eq!(image.0, Image::new_solid(BLUE).0);
+}
+
+#[test]
+fn primitives_match_canvas() {
+    let mut image = Image8x8::default();
+    Rectangle::new(Point::new(1, 2), Size::new(5, 3))
+        .into_styled(PrimitiveStyle::with_stroke(Rgb888::RED, 1))
+        .draw(&mut image)
+        .unwrap();
+    Line::new(Point::new(-4, 7), Point::new(12, 7))
+        .into_styled(PrimitiveStyle::with_stroke(Rgb888::BLUE, 1))
+        .draw(&mut image)
+        .unwrap();
+    let mut expected = Image::default();
+    expected.rect(1, 2, 5, 3, RED);
+    expected.hline(0, 7, 7, BLUE);
+    assert_eq!(image.0, expected.0);
+}
+
+#[test]
+fn clipped_shapes_and_text() {
+    let mut image = Image8x8::default();
+    Circle::new(Point::new(-20, -20), 60)
+        .into_styled(PrimitiveStyle::with_fill(Rgb888::GREEN))
+        .draw(&mut image)
+        .unwrap();
+    assert!(image.pixels().iter().all(|&pixel| pixel == Color::from(Rgb888::GREEN)));
+
+    let mut image = Image8x8::default();
+    Text::new("Hi", Point::new(0, 5), MonoTextStyle::new(&FONT_4X6, Rgb888::WHITE)).draw(&mut image).unwrap();
+    let lit = image.pixels().iter().filter(|&&pixel| pixel != Color::default()).count();
+    assert!(lit > 5, "{} pixels lit", lit);
+    assert!((0..8).all(|row| image[(row, 7)] == Color::default()));
+}
@@ -0,0 +1,122 @@
+//! Host tests of the 12-bit colors and of their temporal dithering.
+
+use tp_led_matrix::hdr::{
+    dither, dither_row, HdrColor, HdrGammaCurve, HdrImage, DITHERED_FPS, DITHER_CYCLE_HZ, HDR_FRAME_LEN, HDR_MAX,
+    SUBFRAMES,
+};
+use tp_led_matrix::{Color, Image};
+
+#[test]
+fn extension_round_trip() {
+    for x in 0..=255 {
+        let color = Color { r: x, g: 255 - x, b: x / 2 };
+        assert_eq!(HdrColor::from(color).to_color(), color);
+    }
+    assert_eq!(HdrColor::from(Color { r: 0, g: 0x80, b: 0xff }), HdrColor { r: 0, g: 0x808, b: HDR_MAX });
+}
+
+#[test]
+fn image_conversion() {
+    let image: Image = Image::gradient(Color { r: 0xff, g: 0x80, b: 0x10 });
+    let hdr = HdrImage::from(&image);
+    for row in 0..8 {
+        for col in 0..8 {
+            assert_eq!(hdr[(row, col)].to_color(), image[(row, col)]);
+        }
+        assert_eq!(hdr.row(row)[3], hdr[(row, 3)]);
+    }
+}
+
+#[test]
+fn dither_average_keeps_precision() {
+    for x in 0..=HDR_MAX - 15 {
+        for (row, col) in [(0, 0), (1, 2), (3, 3), (6, 5)] {
+            let sum: u32 = (0..SUBFRAMES).map(|s| dither(x, row, col, s) as u32).sum();
+            assert_eq!(sum, x as u32, "{} at ({}, {})", x, row, col);
+        }
+    }
+}
+
+#[test]
+fn dither_saturates() {
+    for subframe in 0..SUBFRAMES {
+        assert_eq!(dither(HDR_MAX, 2, 7, subframe), 255);
+        assert_eq!(dither(u16::MAX, 0, 0, subframe), 255);
+        assert_eq!(dither(0, 4, 1, subframe), 0);
+    }
+}
+
+#[test]
+fn neighbours_are_spread() {
+    // A fraction of 1/16 is rounded up in a different subframe for every
+    // pixel of a 4×4 block.
+    let mut subframes: Vec<usize> = (0..16)
+        .map(|i| (0..SUBFRAMES).find(|&s| dither(1, i / 4, i % 4, s) == 1).unwrap())
+        .collect();
+    subframes.sort();
+    assert_eq!(subframes, (0..SUBFRAMES).collect::<Vec<_>>());
+}
+
+#[test]
+fn curves_are_monotonic() {
+    for curve in [&HdrGammaCurve::IDENTITY, &HdrGammaCurve::STANDARD, &HdrGammaCurve::SRGB] {
+        assert_eq!((curve.correct(0), curve.correct(HDR_MAX)), (0, HDR_MAX));
+        assert!((0..HDR_MAX).all(|x| curve.correct(x) <= curve.correct(x + 1)));
+    }
+    assert_eq!(HdrGammaCurve::IDENTITY.correct(1234), 1234);
+}
+
+#[test]
+fn standard_keeps_dim_levels() {
+    // The 8-bit curve maps the inputs 10 to 40 to only 5 output values,
+    // the 12-bit curve keeps them apart.
+    let levels: Vec<u16> = (10..=40).map(|x| HdrGammaCurve::STANDARD.correct(HdrColor::from(Color { r: x, g: 0, b: 0 }).r)).collect();
+    assert!(levels.windows(2).all(|w| w[0] < w[1]), "{:?}", levels);
+}
+
+#[test]
+fn row_dithering() {
+    let pixels = [HdrColor { r: 0x808, g: 0x100, b: 0 }; 8];
+    let mut sums = [0u32; 3];
+    for subframe in 0..SUBFRAMES {
+        let row = dither_row(&pixels, 5, subframe, &HdrGammaCurve::IDENTITY);
+        sums[0] += row[2].r as u32;
+        sums[1] += row[2].g as u32;
+        sums[2] += row[2].b as u32;
+    }
+    assert_eq!(sums, [0x808, 0x100, 0]);
+}
+
+#[test]
+fn dither_cycle_does_not_flicker() {
+    // The whole sequence of subframes must repeat at 60Hz at least
+    assert_eq!(DITHERED_FPS % SUBFRAMES as u32, 0);
+    assert_eq!(DITHER_CYCLE_HZ, 60);
+}
+
+#[test]
+fn packing_round_trip() {
+    let mut image = HdrImage::default();
+    for (i, pixel) in image.0.iter_mut().flatten().enumerate() {
+        *pixel = HdrColor { r: i as u16 * 65, g: HDR_MAX - i as u16, b: 0xabc };
+    }
+    let bytes = image.to_bytes();
+    assert_eq!(bytes.len(), HDR_FRAME_LEN);
+    assert_eq!(bytes[..3], [0x00, 0x0f, 0xff]);
+    assert_eq!(HdrImage::from_bytes(&bytes), image);
+    assert_eq!(image.to_image()[(0, 1)], Color { r: 4, g: 0xff, b: 0xab });
+}
+
+#[test]
+fn odd_channel_counts_are_padded() {
+    let mut image = HdrImage::<3, 1>::default();
+    image[(0, 2)] = HdrColor { r: 1, g: 2, b: 0xabc };
+    let bytes: Vec<u8> = image.packed().collect();
+    assert_eq!(bytes.len(), HdrImage::<3, 1>::BYTES);
+    assert_eq!(bytes[12..], [0xab, 0xc0, 0x00]);
+    let mut received = HdrImage::<3, 1>::default();
+    for (index, &byte) in bytes.iter().enumerate() {
+        received.set_packed(index, byte);
+    }
+    assert_eq!(received, image);
+}
@@ -0,0 +1,127 @@
+//! Golden-trace tests of the panel layouts applied by `Matrix`.
+
+use tp_led_matrix::dot_correction::DotCorrection;
+use tp_led_matrix::gamma::Channel;
+use tp_led_matrix::hdr::HdrImage;
+use tp_led_matrix::layout::{ChannelOrder, ColumnOrder, PanelLayout, Rotation};
+use tp_led_matrix::trace::{Recorder, Transfer};
+use tp_led_matrix::{gamma, Color, Image};
+
+/// Pixel `(row, col)` holds its coordinates.
+fn numbered() -> Image {
+    let mut image = Image::default();
+    for row in 0..8 {
+        for col in 0..8 {
+            image[(row, col)] = Color { r: 0x80 + row as u8, g: 0x90 + col as u8, b: 0xff };
+        }
+    }
+    image
+}
+
+/// Display `image` with `layout` and return the latched rows.
+fn display(layout: PanelLayout, image: &Image) -> Vec<Transfer> {
+    let recorder = Recorder::new();
+    let mut matrix = recorder.matrix();
+    matrix.set_layout(layout);
+    recorder.clear();
+    matrix.display_image(image);
+    recorder.decode()
+}
+
+fn data(transfer: &Transfer) -> [u8; 24] {
+    match transfer {
+        Transfer::Row { data, .. } => *data,
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn default_layout_is_unchanged() {
+    let image = numbered();
+    let transfers = display(PanelLayout::default(), &image);
+    for (row, transfer) in transfers.iter().enumerate() {
+        let mut expected = [0; 24];
+        for (i, pixel) in image.row(row).iter().rev().enumerate() {
+            expected[3 * i..3 * i + 3].copy_from_slice(&[pixel.b, pixel.g, pixel.r].map(gamma::gamma_correct));
+        }
+        assert_eq!(*transfer, Transfer::Row { row: Some(row), data: expected });
+    }
+}
+
+#[test]
+fn column_and_channel_orders() {
+    let pixels = [0, 1, 2, 3, 4, 5, 6, 7].map(|i| Color { r: 0x10 + i, g: 0x20 + i, b: 0x30 + i });
+    let layout = |column_order, channel_order| PanelLayout { column_order, channel_order, ..PanelLayout::DEFAULT };
+    let data = layout(ColumnOrder::Forward, ChannelOrder::Rgb).row_data(&pixels);
+    assert_eq!(data[..6], [0x10, 0x20, 0x30, 0x11, 0x21, 0x31]);
+    let data = layout(ColumnOrder::Reversed, ChannelOrder::Grb).row_data(&pixels);
+    assert_eq!(data[..6], [0x27, 0x17, 0x37, 0x26, 0x16, 0x36]);
+    let data = layout(ColumnOrder::Reversed, ChannelOrder::Bgr).row_data(&pixels);
+    assert_eq!(data[21..], [0x30, 0x20, 0x10]);
+}
+
+#[test]
+fn row_pins_are_permuted() {
+    let row_pins = [7, 6, 5, 4, 3, 2, 1, 0];
+    let transfers = display(PanelLayout { row_pins, ..PanelLayout::DEFAULT }, &numbered());
+    let rows: Vec<_> = transfers
+        .iter()
+        .map(|t| match t {
+            Transfer::Row { row, .. } => *row,
+            _ => None,
+        })
+        .collect();
+    assert_eq!(rows, [7, 6, 5, 4, 3, 2, 1, 0].map(Some));
+    assert_eq!(data(&transfers[0]), data(&display(PanelLayout::DEFAULT, &numbered())[0]));
+}
+
+#[test]
+fn orientation_is_applied_to_images() {
+    let image = numbered();
+    let rotated = PanelLayout { rotation: Rotation::R90, ..PanelLayout::DEFAULT };
+    assert_eq!(display(rotated, &image), display(PanelLayout::DEFAULT, &image.rotate90()));
+    let mirrored = PanelLayout { rotation: Rotation::R180, mirror: true, ..PanelLayout::DEFAULT };
+    assert_eq!(mirrored.orient(&image), image.flip_vertical());
+    assert_eq!(PanelLayout::DEFAULT.orient(&image), image);
+}
+
+#[test]
+fn orientation_is_applied_to_hdr_images() {
+    let image = numbered();
+    for rotation in [Rotation::R0, Rotation::R90, Rotation::R180, Rotation::R270] {
+        for mirror in [false, true] {
+            let layout = PanelLayout { rotation, mirror, ..PanelLayout::DEFAULT };
+            assert_eq!(layout.orient_hdr(&HdrImage::from(&image)), HdrImage::from(&layout.orient(&image)));
+        }
+    }
+}
+
+#[test]
+fn dot_correction_follows_the_layout() {
+    let recorder = Recorder::new();
+    let mut matrix = recorder.matrix();
+    let mut calibration = DotCorrection::uniform(0, 0, 0);
+    calibration.set(0, Channel::Red, 1);
+    calibration.set(0, Channel::Green, 2);
+    calibration.set(7, Channel::Blue, 3);
+    matrix.set_dot_correction(calibration);
+    recorder.clear();
+    // Column 0 first, in G, R, B order
+    matrix.set_layout(PanelLayout {
+        column_order: ColumnOrder::Forward,
+        channel_order: ChannelOrder::Grb,
+        ..PanelLayout::DEFAULT
+    });
+    let mut expected = [0; 24];
+    expected[..3].copy_from_slice(&[2, 1, 0]);
+    expected[23] = 3;
+    assert_eq!(recorder.decode(), vec![Transfer::Bank0(expected)]);
+}
+
+#[test]
+#[should_panic(expected = "permutation")]
+fn duplicate_row_pins_are_rejected() {
+    let recorder = Recorder::new();
+    let mut matrix = recorder.matrix();
+    matrix.set_layout(PanelLayout { row_pins: [0, 1, 2, 3, 4, 5, 6, 6], ..PanelLayout::DEFAULT });
+}
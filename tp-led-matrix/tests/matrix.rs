@@ -0,0 +1,120 @@
+//! Exercise the shift-register sequencing of `Matrix` with mock pins.
+
+use std::cell::RefCell;
+use std::convert::Infallible;
+use std::rc::Rc;
+
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::digital::v2::OutputPin;
+use tp_led_matrix::matrix::Matrix;
+use tp_led_matrix::shift::BitBang;
+use tp_led_matrix::{gamma, Color, Image8x8};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Pin {
+    Sb,
+    Lat,
+    Rst,
+    Sck,
+    Sda,
+    Row(usize),
+}
+
+type Log = Rc<RefCell<Vec<(Pin, bool)>>>;
+
+struct MockPin {
+    pin: Pin,
+    log: Log,
+}
+
+impl OutputPin for MockPin {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        self.log.borrow_mut().push((self.pin, false));
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        self.log.borrow_mut().push((self.pin, true));
+        Ok(())
+    }
+}
+
+struct NoDelay;
+
+impl DelayMs<u8> for NoDelay {
+    fn delay_ms(&mut self, _ms: u8) {}
+}
+
+type MockMatrix = Matrix<MockPin, MockPin, MockPin, BitBang<MockPin, MockPin>, MockPin>;
+
+fn mock_matrix() -> (MockMatrix, Log) {
+    let log = Log::default();
+    let pin = |pin| MockPin { pin, log: log.clone() };
+    let matrix = Matrix::from_pins(
+        pin(Pin::Sb),
+        pin(Pin::Lat),
+        pin(Pin::Rst),
+        pin(Pin::Sck),
+        pin(Pin::Sda),
+        [0, 1, 2, 3, 4, 5, 6, 7].map(|n| pin(Pin::Row(n))),
+        &mut NoDelay,
+    );
+    (matrix, log)
+}
+
+/// Collect the bits present on SDA at every rising edge of SCK.
+fn shifted_bits(log: &[(Pin, bool)]) -> Vec<bool> {
+    let mut sda = false;
+    let mut bits = Vec::new();
+    for &(pin, level) in log {
+        match pin {
+            Pin::Sda => sda = level,
+            Pin::Sck if level => bits.push(sda),
+            _ => {}
+        }
+    }
+    bits
+}
+
+fn to_bytes(bits: &[bool]) -> Vec<u8> {
+    bits.chunks(8)
+        .map(|byte| byte.iter().fold(0, |acc, &bit| acc << 1 | bit as u8))
+        .collect()
+}
+
+#[test]
+fn init_shifts_144_ones_into_bank0() {
+    let (_matrix, log) = mock_matrix();
+    let log = log.borrow();
+    let bits = shifted_bits(&log);
+    assert_eq!(bits.len(), 144);
+    assert!(bits.iter().all(|&bit| bit));
+    let sb_low = log.iter().position(|&e| e == (Pin::Sb, false)).unwrap();
+    let rst_high = log.iter().position(|&e| e == (Pin::Rst, true)).unwrap();
+    let last_lat = log.iter().rposition(|&e| e == (Pin::Lat, false)).unwrap();
+    assert!(rst_high < sb_low && sb_low < last_lat);
+    assert_eq!(log.last(), Some(&(Pin::Sb, true)));
+}
+
+#[test]
+fn send_row_shifts_reversed_bgr_and_switches_rows() {
+    let (mut matrix, log) = mock_matrix();
+    log.borrow_mut().clear();
+    let mut image = Image8x8::default();
+    for col in 0..8 {
+        image[(3, col)] = Color { r: 10 * col as u8, g: 100, b: 255 - col as u8 };
+    }
+    matrix.send_row(3, image.row(3));
+
+    let log = log.borrow();
+    let mut expected = Vec::new();
+    for col in (0..8).rev() {
+        let pixel = image[(3, col)];
+        expected.extend([pixel.b, pixel.g, pixel.r].map(gamma::gamma_correct));
+    }
+    assert_eq!(to_bytes(&shifted_bits(&log)), expected);
+    assert_eq!(log.first(), Some(&(Pin::Row(2), false)));
+    assert_eq!(log[log.len() - 2..], [(Pin::Lat, true), (Pin::Row(3), true)]);
+}
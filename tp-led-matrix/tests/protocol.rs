@@ -0,0 +1,274 @@
+//! Host tests of the SE203 decoder.
+
+use tp_led_matrix::hdr::{HdrColor, HdrImage};
+use tp_led_matrix::protocol::{
+    crc16, encode, encode_hdr, encode_with_checksum, frame_len, Checksum, Decoder, Error, Framing, Packet, Stats,
+    CMD, ESC, FRAME_LEN, HDR_FRAME, SYNC,
+};
+use tp_led_matrix::{Color, Image, Image8x8};
+
+/// Feed bytes to the decoder, collecting the images and errors.
+fn feed(decoder: &mut Decoder, bytes: &[u8]) -> (Vec<Image>, Vec<Error>) {
+    let mut images = Vec::new();
+    let mut errors = Vec::new();
+    for &b in bytes {
+        match decoder.push(b) {
+            Ok(Some(Packet::Image(image))) => images.push(*image),
+            Ok(Some(Packet::Command(_))) => panic!("unexpected command"),
+            Ok(Some(Packet::Hdr(_))) => panic!("unexpected 12-bit frame"),
+            Ok(None) => {}
+            Err(e) => errors.push(e),
+        }
+    }
+    (images, errors)
+}
+
+fn frame(fill: u8) -> Vec<u8> {
+    let mut bytes = vec![SYNC];
+    bytes.extend((0..FRAME_LEN).map(|i| (i as u8).wrapping_add(fill) % 0xff));
+    bytes
+}
+
+#[test]
+fn decodes_one_frame_file() {
+    let bytes = include_bytes!("../one_frame.bin");
+    let mut decoder = Decoder::new();
+    let (images, errors) = feed(&mut decoder, bytes);
+    assert_eq!(images.len(), 1);
+    assert!(errors.is_empty());
+    assert_eq!(images[0].as_ref()[..], bytes[1..]);
+}
+
+#[test]
+fn decodes_consecutive_frames() {
+    let mut decoder = Decoder::new();
+    let bytes = [frame(0), frame(7), frame(42)].concat();
+    let (images, errors) = feed(&mut decoder, &bytes);
+    assert!(errors.is_empty());
+    assert_eq!(images.len(), 3);
+    assert_eq!(images[1].as_ref()[..], frame(7)[1..]);
+    assert_eq!(decoder.stats().frames, 3);
+}
+
+#[test]
+fn drops_bytes_before_first_sync() {
+    let mut decoder = Decoder::new();
+    let bytes = [vec![1, 2, 3], frame(0)].concat();
+    let (images, errors) = feed(&mut decoder, &bytes);
+    assert_eq!((images.len(), errors), (1, vec![]));
+    assert_eq!(decoder.stats().dropped, 3);
+}
+
+#[test]
+fn reports_short_frame() {
+    let mut decoder = Decoder::new();
+    let bytes = [&frame(0)[..100], &frame(1)].concat();
+    let (images, errors) = feed(&mut decoder, &bytes);
+    assert_eq!(errors, vec![Error::ShortFrame(99)]);
+    assert_eq!(images.len(), 1);
+    assert_eq!(images[0].as_ref()[..], frame(1)[1..]);
+}
+
+#[test]
+fn reports_overrun_once_and_resynchronizes() {
+    let mut decoder = Decoder::new();
+    let bytes = [frame(0), vec![0; 300], frame(5)].concat();
+    let (images, errors) = feed(&mut decoder, &bytes);
+    assert_eq!(errors, vec![Error::Overrun]);
+    assert_eq!(images.len(), 2);
+    assert_eq!(
+        *decoder.stats(),
+        Stats { frames: 2, overruns: 1, short_frames: 0, dropped: 300, ..Stats::default() }
+    );
+}
+
+#[test]
+fn survives_garbage_in_final_file() {
+    let mut decoder = Decoder::new();
+    let (images, _) = feed(&mut decoder, include_bytes!("../final.bin"));
+    let stats = decoder.stats();
+    assert_eq!(images.len() as u32, stats.frames);
+    assert_eq!((stats.frames, stats.overruns, stats.short_frames), (1197, 17, 0));
+}
+
+fn encoded(image: &Image, framing: Framing) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    encode(image, framing, |b| bytes.push(b));
+    bytes
+}
+
+/// Image using every byte value, including `SYNC` and `ESC`.
+fn all_values() -> Image {
+    let mut image = Image::default();
+    for (i, b) in image.as_mut().iter_mut().enumerate() {
+        *b = 255 - i as u8;
+    }
+    image
+}
+
+#[test]
+fn legacy_encoding_clamps_sync() {
+    let bytes = encoded(&all_values(), Framing::Legacy);
+    assert_eq!(bytes.len(), 1 + FRAME_LEN);
+    assert_eq!(bytes[..3], [SYNC, 0xfe, 0xfe]);
+    assert_eq!(bytes.iter().filter(|&&b| b == SYNC).count(), 1);
+}
+
+#[test]
+fn stuffed_encoding_round_trips_every_value() {
+    let image = all_values();
+    let bytes = encoded(&image, Framing::Stuffed);
+    assert_eq!(bytes[..7], [SYNC, ESC, 0xdf, ESC, 0xde, ESC, 0xdd]);
+    assert_eq!(bytes.len(), 1 + FRAME_LEN + 3);
+    assert_eq!(bytes.iter().filter(|&&b| b == SYNC).count(), 1);
+
+    let mut decoder = Decoder::with_framing(Framing::Stuffed);
+    let (images, errors) = feed(&mut decoder, &[bytes.clone(), bytes].concat());
+    assert!(errors.is_empty());
+    assert_eq!(images.len(), 2);
+    assert_eq!(images[0].as_ref(), image.as_ref());
+}
+
+#[test]
+fn stuffed_decoder_rejects_invalid_escape() {
+    let mut decoder = Decoder::with_framing(Framing::Stuffed);
+    let mut bytes = encoded(&all_values(), Framing::Stuffed);
+    bytes[2] = 0x00;
+    let (images, errors) = feed(&mut decoder, &bytes);
+    assert!(images.is_empty());
+    assert_eq!(errors, vec![Error::InvalidEscape(0x00)]);
+    assert_eq!(decoder.stats().invalid_escapes, 1);
+}
+
+#[test]
+fn sync_after_escape_is_a_short_frame() {
+    let mut decoder = Decoder::with_framing(Framing::Stuffed);
+    let bytes = [&[SYNC, ESC][..], &encoded(&all_values(), Framing::Stuffed)].concat();
+    let (images, errors) = feed(&mut decoder, &bytes);
+    assert_eq!(errors, vec![Error::ShortFrame(0)]);
+    assert_eq!(images.len(), 1);
+}
+
+#[test]
+fn crc16_matches_reference_value() {
+    assert_eq!(crc16(b"123456789"), 0x29b1);
+}
+
+#[test]
+fn checksummed_frames_round_trip() {
+    let image = all_values();
+    let mut bytes = Vec::new();
+    encode_with_checksum(&image, Framing::Stuffed, Checksum::Crc16, |b| bytes.push(b));
+    let mut decoder = Decoder::with_checksum(Framing::Stuffed, Checksum::Crc16);
+    let (images, errors) = feed(&mut decoder, &bytes);
+    assert!(errors.is_empty());
+    assert_eq!(images.len(), 1);
+    assert_eq!(images[0].as_ref(), image.as_ref());
+}
+
+#[test]
+fn corrupted_frame_fails_checksum() {
+    let mut bytes = Vec::new();
+    encode_with_checksum(&Image8x8::gradient(Color { r: 1, g: 2, b: 3 }), Framing::Stuffed, Checksum::Crc16, |b| bytes.push(b));
+    bytes[100] ^= 0x01;
+    let mut decoder = Decoder::with_checksum(Framing::Stuffed, Checksum::Crc16);
+    let (images, errors) = feed(&mut decoder, &bytes);
+    assert!(images.is_empty());
+    assert_eq!(errors, vec![Error::ChecksumMismatch]);
+    assert_eq!((decoder.stats().frames, decoder.stats().checksum_errors), (0, 1));
+}
+
+#[test]
+fn wide_frames_round_trip() {
+    let mut image = Image::<16, 8>::gradient(Color { r: 200, g: 100, b: 50 });
+    image[(7, 15)] = Color { r: 0xff, g: 0xfe, b: 0xfd };
+    let mut bytes = Vec::new();
+    encode_with_checksum(&image, Framing::Stuffed, Checksum::Crc16, |b| bytes.push(b));
+    assert_eq!(frame_len(16, 8), 2 * FRAME_LEN);
+    assert_eq!(bytes.len(), 1 + frame_len(16, 8) + 3 + 2);
+
+    let mut decoder = Decoder::<16, 8>::sized(Framing::Stuffed, Checksum::Crc16);
+    let mut received = Vec::new();
+    for &b in &bytes {
+        if let Some(Packet::Image(image)) = decoder.push(b).unwrap() {
+            received.push(*image);
+        }
+    }
+    assert_eq!(received, vec![image]);
+}
+
+#[test]
+fn tall_frame_overruns_a_small_decoder() {
+    let mut bytes = Vec::new();
+    encode(&Image::<8, 16>::default(), Framing::Legacy, |b| bytes.push(b));
+    let mut decoder = Decoder::new();
+    let (images, errors) = feed(&mut decoder, &[bytes, vec![SYNC]].concat());
+    assert_eq!(images.len(), 1);
+    assert_eq!(errors, vec![Error::Overrun]);
+}
+
+/// 12-bit image using every channel value, reserved bytes included once
+/// packed.
+fn hdr_ramp() -> HdrImage {
+    let mut image = HdrImage::default();
+    for (i, pixel) in image.0.iter_mut().flatten().enumerate() {
+        let i = i as u16 * 64;
+        *pixel = HdrColor { r: i, g: 4095 - i, b: 0xffd };
+    }
+    image
+}
+
+#[test]
+fn hdr_frames_round_trip_between_images() {
+    let image = hdr_ramp();
+    let mut bytes = Vec::new();
+    encode_hdr(&image, Checksum::Crc16, |b| bytes.push(b));
+    assert_eq!(bytes[..2], [CMD, HDR_FRAME]);
+    encode_with_checksum(&all_values(), Framing::Stuffed, Checksum::Crc16, |b| bytes.push(b));
+    encode_hdr(&image, Checksum::Crc16, |b| bytes.push(b));
+
+    let mut decoder = Decoder::with_checksum(Framing::Stuffed, Checksum::Crc16);
+    let mut received = Vec::new();
+    for b in bytes {
+        match decoder.push(b).unwrap() {
+            Some(Packet::Hdr(hdr)) => received.push(Some(*hdr)),
+            Some(Packet::Image(_)) => received.push(None),
+            Some(Packet::Command(_)) => panic!("unexpected command"),
+            None => {}
+        }
+    }
+    assert_eq!(received, vec![Some(image), None, Some(image)]);
+    assert_eq!((decoder.stats().frames, decoder.stats().commands), (3, 0));
+}
+
+#[test]
+fn hdr_frames_have_the_size_of_the_decoder() {
+    let mut image = HdrImage::<16, 8>::default();
+    image[(0, 0)].r = 0xfed;
+    image[(7, 15)] = HdrColor { r: 0x123, g: 0xfff, b: 0x0ff };
+    let mut bytes = Vec::new();
+    encode_hdr(&image, Checksum::Crc16, |b| bytes.push(b));
+    let mut decoder = Decoder::<16, 8>::sized(Framing::Stuffed, Checksum::Crc16);
+    let mut received = None;
+    for b in bytes {
+        if let Some(Packet::Hdr(hdr)) = decoder.push(b).unwrap() {
+            received = Some(*hdr);
+        }
+    }
+    assert_eq!(received, Some(image));
+}
+
+#[test]
+fn interrupted_hdr_frame_is_short() {
+    let mut bytes = Vec::new();
+    encode_hdr(&hdr_ramp(), Checksum::None, |b| bytes.push(b));
+    let mut decoder = Decoder::with_framing(Framing::Stuffed);
+    let (images, errors) = feed(&mut decoder, &[&bytes[..2], &frame(0)[..]].concat());
+    assert_eq!((images.len(), errors), (1, vec![Error::ShortFrame(0)]));
+}
+
+#[test]
+#[should_panic]
+fn checksum_requires_stuffed_framing() {
+    Decoder::with_checksum(Framing::Legacy, Checksum::Crc16);
+}
@@ -0,0 +1,62 @@
+//! Host tests of the fixed-point color math against the float versions.
+
+use tp_led_matrix::image::{self, scale8, RED, WHITE};
+use tp_led_matrix::{Color, Image8x8};
+
+fn within_one(a: Color, b: Color) -> bool {
+    [(a.r, b.r), (a.g, b.g), (a.b, b.b)].iter().all(|&(x, y)| x.abs_diff(y) <= 1)
+}
+
+#[test]
+fn scale8_matches_float_scaling() {
+    for x in 0..=255 {
+        for scale in 0..=255 {
+            let color = Color { r: x, g: 255 - x, b: x / 3 };
+            assert_eq!(color.scale8(scale), color * (scale as f32 / 255.0), "{:?} by {}", color, scale);
+        }
+    }
+}
+
+#[test]
+fn scale8_endpoints() {
+    for x in 0..=255 {
+        assert_eq!(scale8(x, 255), x);
+        assert_eq!(scale8(x, 0), 0);
+        assert_eq!(scale8(255, x), x);
+    }
+}
+
+#[test]
+fn lerp8_matches_float_lerp() {
+    let pairs = [(RED, image::BLUE), (image::BLACK, WHITE), (image::ORANGE, image::TEAL)];
+    for (a, b) in pairs {
+        assert_eq!(Color::lerp8(a, b, 0), a);
+        assert_eq!(Color::lerp8(a, b, 255), b);
+        for t in 0..=255 {
+            let float = Color::lerp(a, b, t as f32 / 255.0);
+            assert!(within_one(Color::lerp8(a, b, t), float), "{:?} to {:?} at {}", a, b, t);
+        }
+    }
+}
+
+#[test]
+fn gradient_matches_float_division() {
+    for x in 0..=255 {
+        let color = Color { r: x, g: 255 - x, b: 0x80 };
+        let image = Image8x8::gradient(color);
+        for row in 0..8 {
+            for col in 0..8 {
+                assert_eq!(image[(row, col)], color / (1 + row * row + col) as f32);
+            }
+        }
+    }
+}
+
+#[test]
+fn image_scale8() {
+    let image = Image8x8::gradient(WHITE);
+    let scaled = image.scale8(100);
+    for (pixel, original) in scaled.pixels().iter().zip(image.pixels().iter()) {
+        assert_eq!(*pixel, original.scale8(100));
+    }
+}
@@ -0,0 +1,70 @@
+//! Host tests of the tiling of a virtual canvas over several modules.
+
+use tp_led_matrix::draw::Canvas;
+use tp_led_matrix::image::{BLUE, RED};
+use tp_led_matrix::tile::{ChainOrder, Tile, Tiling};
+use tp_led_matrix::{Color, Image, Image8x8};
+
+/// Pixel `(row, col)` holds its coordinates.
+fn numbered<const W: usize, const H: usize>() -> Image<W, H> {
+    let mut image = Image::default();
+    for row in 0..H {
+        for col in 0..W {
+            image[(row, col)] = Color { r: row as u8, g: col as u8, b: 0xff };
+        }
+    }
+    image
+}
+
+#[test]
+fn grid_places_modules_in_chain_order() {
+    let tiles = |order| Tiling::<6>::grid(3, order).tiles().map(|tile| (tile.x, tile.y));
+    assert_eq!(tiles(ChainOrder::RowMajor), [(0, 0), (1, 0), (2, 0), (0, 1), (1, 1), (2, 1)]);
+    assert_eq!(tiles(ChainOrder::Serpentine), [(0, 0), (1, 0), (2, 0), (2, 1), (1, 1), (0, 1)]);
+}
+
+#[test]
+fn split_cuts_the_canvas_into_modules() {
+    let image = numbered::<16, 16>();
+    let modules = Tiling::<4>::grid(2, ChainOrder::Serpentine).split(&image);
+    for (module, (top, left)) in modules.iter().zip([(0, 0), (0, 8), (8, 8), (8, 0)]) {
+        for row in 0..8 {
+            for col in 0..8 {
+                assert_eq!(module[(row, col)], image[(top + row, left + col)]);
+            }
+        }
+    }
+}
+
+#[test]
+fn join_reverses_split() {
+    let image = numbered::<24, 8>();
+    let tiling = Tiling::<3>::new([Tile { x: 2, y: 0 }, Tile { x: 0, y: 0 }, Tile { x: 1, y: 0 }]);
+    let modules = tiling.split(&image);
+    assert_eq!(modules[0][(0, 0)], image[(0, 16)]);
+    assert_eq!(tiling.join::<24, 8>(&modules), image);
+}
+
+#[test]
+fn pixels_outside_the_canvas_are_black() {
+    let image = Image::<12, 8>::new_solid(RED);
+    let modules = Tiling::<2>::grid(2, ChainOrder::RowMajor).split(&image);
+    assert_eq!(modules[0], Image8x8::new_solid(RED));
+    for row in 0..8 {
+        for col in 0..8 {
+            let expected = if col < 4 { RED } else { Color::default() };
+            assert_eq!(modules[1][(row, col)], expected);
+        }
+    }
+}
+
+#[test]
+fn shapes_span_modules() {
+    let mut image = Image::<16, 8>::default();
+    image.hline(0, 15, 3, BLUE);
+    let modules = Tiling::<2>::grid(2, ChainOrder::RowMajor).split(&image);
+    for module in &modules {
+        assert!(module.row(3).iter().all(|&pixel| pixel == BLUE));
+        assert_eq!(module.pixels().iter().filter(|&&pixel| pixel == BLUE).count(), 8);
+    }
+}
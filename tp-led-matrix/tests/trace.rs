@@ -0,0 +1,182 @@
+//! Golden-trace tests of the DM163 sequencing of `Matrix`.
+
+use embedded_hal::digital::v2::OutputPin;
+use tp_led_matrix::dot_correction::{DotCorrection, WhiteBalance};
+use tp_led_matrix::gamma::Channel;
+use tp_led_matrix::trace::{Recorder, Signal, Transfer};
+use tp_led_matrix::{gamma, Color, Image};
+
+fn expected_row(pixels: &[Color]) -> [u8; 24] {
+    let mut data = [0; 24];
+    for (i, pixel) in pixels.iter().rev().enumerate() {
+        data[3 * i..3 * i + 3].copy_from_slice(&[pixel.b, pixel.g, pixel.r].map(gamma::gamma_correct));
+    }
+    data
+}
+
+#[test]
+fn init_latches_full_dot_correction() {
+    let recorder = Recorder::new();
+    let _matrix = recorder.matrix();
+    assert_eq!(recorder.decode(), vec![Transfer::Bank0([0x3f; 24])]);
+}
+
+#[test]
+fn brightness_latches_scaled_dot_correction_once() {
+    let recorder = Recorder::new();
+    let mut matrix = recorder.matrix();
+    recorder.clear();
+    matrix.set_white_balance(WhiteBalance { red: 63, green: 40, blue: 20 });
+    matrix.set_brightness(128);
+    matrix.set_brightness(128);
+    let full = [20, 40, 63].repeat(8);
+    let half = [10, 20, 32].repeat(8);
+    assert_eq!(
+        recorder.decode(),
+        vec![Transfer::Bank0(full.try_into().unwrap()), Transfer::Bank0(half.try_into().unwrap())]
+    );
+}
+
+#[test]
+fn dot_correction_is_shifted_from_the_last_column() {
+    let recorder = Recorder::new();
+    let mut matrix = recorder.matrix();
+    recorder.clear();
+    let mut dot_correction = DotCorrection::FULL;
+    dot_correction.set(0, Channel::Red, 1);
+    dot_correction.set(7, Channel::Blue, 2);
+    matrix.set_dot_correction(dot_correction);
+    let mut expected = [0x3f; 24];
+    expected[0] = 2;
+    expected[23] = 1;
+    assert_eq!(recorder.decode(), vec![Transfer::Bank0(expected)]);
+}
+
+#[test]
+fn display_image_latches_every_row_then_switches_it_on() {
+    let recorder = Recorder::new();
+    let mut matrix = recorder.matrix();
+    recorder.clear();
+    let image = Image::gradient(Color { r: 0xff, g: 0x80, b: 0x20 });
+    matrix.display_image(&image);
+
+    let expected: Vec<_> = (0..8)
+        .map(|row| Transfer::Row { row: Some(row), data: expected_row(image.row(row)) })
+        .collect();
+    assert_eq!(recorder.decode(), expected);
+}
+
+#[test]
+fn vcd_dump_only_contains_changes() {
+    let recorder = Recorder::new();
+    let mut sck = recorder.pin(Signal::Sck);
+    let mut c7 = recorder.pin(Signal::Row(7));
+    sck.set_low().unwrap();
+    sck.set_low().unwrap();
+    c7.set_high().unwrap();
+    sck.set_high().unwrap();
+
+    let mut vcd = Vec::new();
+    recorder.write_vcd(&mut vcd).unwrap();
+    let golden = "\
+$version tp-led-matrix trace $end
+$timescale 1us $end
+$scope module dm163 $end
+$var wire 1 ! SB $end
+$var wire 1 \" LAT $end
+$var wire 1 # RST $end
+$var wire 1 $ SCK $end
+$var wire 1 % SDA $end
+$var wire 1 & C0 $end
+$var wire 1 ' C1 $end
+$var wire 1 ( C2 $end
+$var wire 1 ) C3 $end
+$var wire 1 * C4 $end
+$var wire 1 + C5 $end
+$var wire 1 , C6 $end
+$var wire 1 - C7 $end
+$upscope $end
+$enddefinitions $end
+$dumpvars
+x!
+x\"
+x#
+x$
+x%
+x&
+x'
+x(
+x)
+x*
+x+
+x,
+x-
+$end
+#0
+0$
+#2
+1-
+#3
+1$
+";
+    assert_eq!(String::from_utf8(vcd).unwrap(), golden);
+}
+
+#[test]
+fn short_latch_is_reported_as_malformed() {
+    let recorder = Recorder::new();
+    let mut lat = recorder.pin(Signal::Lat);
+    let mut sck = recorder.pin(Signal::Sck);
+    let mut sda = recorder.pin(Signal::Sda);
+    let mut sb = recorder.pin(Signal::Sb);
+    sb.set_high().unwrap();
+    lat.set_high().unwrap();
+    sck.set_low().unwrap();
+    sda.set_high().unwrap();
+    sck.set_high().unwrap();
+    lat.set_low().unwrap();
+    assert_eq!(recorder.decode(), vec![Transfer::Malformed { sb: true, bits: vec![true] }]);
+}
+
+#[test]
+fn spi_backend_latches_the_same_data() {
+    let image = Image::gradient(Color { r: 0x40, g: 0xff, b: 0x10 });
+    let mut transfers = Vec::new();
+    for spi in [false, true] {
+        let recorder = Recorder::new();
+        if spi {
+            let mut matrix = recorder.spi_matrix();
+            matrix.set_brightness(100);
+            matrix.display_image(&image);
+        } else {
+            let mut matrix = recorder.matrix();
+            matrix.set_brightness(100);
+            matrix.display_image(&image);
+        }
+        transfers.push(recorder.decode());
+    }
+    assert_eq!(transfers[0].len(), 10);
+    assert_eq!(transfers[0], transfers[1]);
+}
+
+#[test]
+fn started_rows_are_latched_when_finished() {
+    let recorder = Recorder::new();
+    let mut matrix = recorder.matrix();
+    recorder.clear();
+    let image: Image = Image::gradient(Color { r: 0x20, g: 0x40, b: 0xff });
+    matrix.start_row(0, image.row(0));
+    assert_eq!(recorder.decode(), vec![]);
+    matrix.finish_row();
+    // A pending row is latched before the next one or a bank 0 update
+    matrix.start_row(1, image.row(1));
+    matrix.start_row(2, image.row(2));
+    matrix.set_brightness(128);
+    matrix.finish_row();
+
+    let transfers = recorder.decode();
+    let rows: Vec<_> = (0..3).map(|row| Transfer::Row { row: Some(row), data: expected_row(image.row(row)) }).collect();
+    assert_eq!(transfers[..3], rows[..]);
+    assert!(matches!(transfers[3], Transfer::Bank0(_)));
+    assert_eq!(transfers.len(), 4);
+}
@@ -0,0 +1,138 @@
+//! Exhaustive host tests of the geometric transforms, on an image whose
+//! pixels are all different.
+
+use tp_led_matrix::hdr::HdrImage;
+use tp_led_matrix::image::{BLUE, RED};
+use tp_led_matrix::transform::Edge;
+use tp_led_matrix::{Color, Image};
+
+/// Pixel `(row, col)` holds its coordinates.
+fn numbered() -> Image {
+    let mut image = Image::default();
+    for row in 0..8 {
+        for col in 0..8 {
+            image[(row, col)] = Color { r: row as u8, g: col as u8, b: 0xff };
+        }
+    }
+    image
+}
+
+/// Coordinates of the pixel of `numbered()` found at `(row, col)`.
+fn source(image: &Image, row: usize, col: usize) -> (usize, usize) {
+    let pixel = image[(row, col)];
+    (pixel.r as usize, pixel.g as usize)
+}
+
+#[test]
+fn rotations_move_every_pixel() {
+    let image = numbered();
+    let (r90, r180, r270) = (image.rotate90(), image.rotate180(), image.rotate270());
+    for row in 0..8 {
+        for col in 0..8 {
+            assert_eq!(source(&r90, row, col), (7 - col, row));
+            assert_eq!(source(&r180, row, col), (7 - row, 7 - col));
+            assert_eq!(source(&r270, row, col), (col, 7 - row));
+        }
+    }
+    // The top-left corner goes to the top-right one
+    assert_eq!(source(&r90, 0, 7), (0, 0));
+}
+
+#[test]
+fn rotation_compositions() {
+    let image = numbered();
+    assert_eq!(image.rotate90().rotate90().rotate90().rotate90(), image);
+    assert_eq!(image.rotate90().rotate90(), image.rotate180());
+    assert_eq!(image.rotate90().rotate180(), image.rotate270());
+    assert_eq!(image.rotate270().rotate90(), image);
+    assert_eq!(image.rotate180().rotate180(), image);
+}
+
+#[test]
+fn flips_and_transpose() {
+    let image = numbered();
+    for row in 0..8 {
+        for col in 0..8 {
+            assert_eq!(source(&image.flip_horizontal(), row, col), (row, 7 - col));
+            assert_eq!(source(&image.flip_vertical(), row, col), (7 - row, col));
+            assert_eq!(source(&image.transpose(), row, col), (col, row));
+        }
+    }
+    assert_eq!(image.flip_horizontal().flip_horizontal(), image);
+    assert_eq!(image.flip_vertical().flip_vertical(), image);
+    assert_eq!(image.transpose().transpose(), image);
+    assert_eq!(image.flip_horizontal().flip_vertical(), image.rotate180());
+    assert_eq!(image.transpose().flip_horizontal(), image.rotate90());
+    assert_eq!(image.transpose().flip_vertical(), image.rotate270());
+}
+
+#[test]
+fn wrapping_shifts() {
+    let image = numbered();
+    for dx in -16..=16 {
+        for dy in -16..=16 {
+            let shifted = image.shift(dx, dy, Edge::Wrap);
+            assert_eq!(shifted.shift(-dx, -dy, Edge::Wrap), image, "by ({}, {})", dx, dy);
+            let expected = ((-dy).rem_euclid(8) as usize, (-dx).rem_euclid(8) as usize);
+            assert_eq!(source(&shifted, 0, 0), expected, "by ({}, {})", dx, dy);
+        }
+    }
+    assert_eq!(image.shift(8, -8, Edge::Wrap), image);
+}
+
+#[test]
+fn filling_shifts() {
+    let image = numbered();
+    for dx in -9..=9 {
+        for dy in -9..=9 {
+            let shifted = image.shift(dx, dy, Edge::Fill(RED));
+            for row in 0..8 {
+                for col in 0..8 {
+                    let (src_row, src_col) = (row as i32 - dy, col as i32 - dx);
+                    if (0..8).contains(&src_row) && (0..8).contains(&src_col) {
+                        assert_eq!(source(&shifted, row, col), (src_row as usize, src_col as usize));
+                    } else {
+                        assert_eq!(shifted[(row, col)], RED);
+                    }
+                }
+            }
+        }
+    }
+    assert_eq!(image.shift(0, 0, Edge::Fill(BLUE)), image);
+    assert_eq!(image.shift(8, 0, Edge::Fill(BLUE)), Image::new_solid(BLUE));
+}
+
+#[test]
+fn rotations_of_wide_images_swap_their_size() {
+    let mut image = Image::<16, 8>::default();
+    image[(0, 15)] = RED;
+    let rotated: Image<8, 16> = image.rotate90();
+    assert_eq!(rotated[(15, 7)], RED);
+    assert_eq!(image.rotate270()[(0, 0)], RED);
+    assert_eq!(image.transpose()[(15, 0)], RED);
+    assert_eq!(image.rotate90().rotate270(), image);
+    assert_eq!(image.shift(1, 0, Edge::Wrap)[(0, 0)], RED);
+}
+
+#[test]
+fn hdr_images_are_transformed_like_images() {
+    let mut image = Image::<16, 8>::default();
+    image[(0, 15)] = RED;
+    image[(7, 1)] = BLUE;
+    let hdr = HdrImage::from(&image);
+    assert_eq!(hdr.rotate90(), HdrImage::from(&image.rotate90()));
+    assert_eq!(hdr.rotate180(), HdrImage::from(&image.rotate180()));
+    assert_eq!(hdr.rotate270(), HdrImage::from(&image.rotate270()));
+    assert_eq!(hdr.flip_horizontal(), HdrImage::from(&image.flip_horizontal()));
+    assert_eq!(hdr.flip_vertical(), HdrImage::from(&image.flip_vertical()));
+    assert_eq!(hdr.transpose(), HdrImage::from(&image.transpose()));
+}
+
+#[test]
+fn extreme_shifts() {
+    let image = numbered();
+    assert_eq!(image.shift(i32::MIN, i32::MIN, Edge::Wrap), image);
+    assert_eq!(image.shift(i32::MAX, i32::MAX, Edge::Wrap), image.shift(-1, -1, Edge::Wrap));
+    assert_eq!(image.shift(i32::MIN, 0, Edge::Fill(BLUE)), Image::new_solid(BLUE));
+    assert_eq!(image.shift(0, i32::MAX, Edge::Fill(BLUE)), Image::new_solid(BLUE));
+}